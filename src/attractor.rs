@@ -0,0 +1,102 @@
+use macroquad::prelude::*;
+
+use crate::coords::world_to_screen;
+use crate::force::Force;
+use crate::particle::Particle;
+
+pub const ATTRACTOR_STRENGTH: f32 = 50.0; // m³/s²: pull of 50 m/s² at 1 m, falling with distance squared
+pub const ATTRACTOR_SOFTENING: f32 = 1.0; // ε (m) in d² + ε², caps the pull near the center
+
+const MARKER_RADIUS: f32 = 8.0; // Pixels, whatever the zoom
+
+// === Attractors ===
+// A fixed point pulling every particle toward it (or, with negative
+// strength, pushing it away) with acceleration strength / (d² + ε²), so it
+// acts on light and heavy particles alike and stays finite at the center.
+// Any number of them simply add up.
+pub struct PointAttractor {
+    pub position: Vec2,
+    pub strength: f32, // Negative for a repeller
+    pub softening: f32,
+}
+
+impl PointAttractor {
+    pub fn new(position: Vec2, strength: f32) -> Self {
+        Self {
+            position,
+            strength,
+            softening: ATTRACTOR_SOFTENING,
+        }
+    }
+
+    // Green ring with a plus for an attractor, red with a minus for a repeller.
+    pub fn draw(&self) {
+        let center = world_to_screen(self.position);
+        let (color, plus) = if self.strength >= 0.0 {
+            (GREEN, true)
+        } else {
+            (RED, false)
+        };
+        draw_circle_lines(center.x, center.y, MARKER_RADIUS, 2.0, color);
+        let arm = MARKER_RADIUS * 0.6;
+        draw_line(
+            center.x - arm,
+            center.y,
+            center.x + arm,
+            center.y,
+            2.0,
+            color,
+        );
+        if plus {
+            draw_line(
+                center.x,
+                center.y - arm,
+                center.x,
+                center.y + arm,
+                2.0,
+                color,
+            );
+        }
+    }
+}
+
+impl Force for PointAttractor {
+    fn apply(&self, particle: &Particle, _particles: &[Particle]) -> Vec2 {
+        let delta = self.position - particle.position;
+        let distance_sq = delta.length_squared() + self.softening * self.softening;
+        delta.normalize_or_zero() * self.strength / distance_sq
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(position: Vec2) -> Particle {
+        Particle::new(position, Vec2::ZERO, 0.5, 1.0, WHITE)
+    }
+
+    #[test]
+    fn attractor_pulls_with_inverse_square_falloff() {
+        let attractor = PointAttractor {
+            position: Vec2::ZERO,
+            strength: 50.0,
+            softening: 0.0,
+        };
+        let near = attractor.apply(&at(Vec2::new(2.0, 0.0)), &[]);
+        assert!(near.abs_diff_eq(Vec2::new(-12.5, 0.0), 1e-5), "{near}");
+        let far = attractor.apply(&at(Vec2::new(0.0, -4.0)), &[]);
+        assert!(far.abs_diff_eq(Vec2::new(0.0, 50.0 / 16.0), 1e-5), "{far}");
+    }
+
+    #[test]
+    fn repeller_pushes_away_and_stays_finite_at_the_center() {
+        let repeller = PointAttractor::new(Vec2::new(5.0, 5.0), -ATTRACTOR_STRENGTH);
+        let push = repeller.apply(&at(Vec2::new(6.0, 5.0)), &[]);
+        assert!(push.x > 0.0 && push.y == 0.0, "{push}");
+        // Softening caps it at strength / ε² however close the particle gets
+        let close = repeller.apply(&at(Vec2::new(5.0 + 1e-6, 5.0)), &[]);
+        assert!(close.length() <= ATTRACTOR_STRENGTH / ATTRACTOR_SOFTENING.powi(2));
+        assert_eq!(repeller.apply(&at(Vec2::new(5.0, 5.0)), &[]), Vec2::ZERO);
+    }
+}
@@ -0,0 +1,142 @@
+use macroquad::prelude::*;
+
+use crate::coords::{pixels_per_meter, world_dimensions, world_to_screen};
+
+pub const BOUNDARY_PADDING: f32 = 1.0;
+
+// === Boundary ===
+// The container particles bounce around in. The rectangle is always kept
+// (it is the window's extent); a circular container sits inside it.
+pub struct Boundary {
+    pub left: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub top: f32,
+    pub shape: BoundaryShape,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BoundaryShape {
+    Rect,
+    Circle { center: Vec2, radius: f32 },
+}
+
+impl Boundary {
+    // Walls inset from the edges of the world.
+    pub fn new() -> Self {
+        let world = world_dimensions();
+        Self::from_dimensions(world.x, world.y)
+    }
+
+    // Walls inset from a world of the given size, independent of any window.
+    pub fn from_dimensions(width: f32, height: f32) -> Self {
+        Self {
+            left: BOUNDARY_PADDING,
+            right: width - BOUNDARY_PADDING,
+            bottom: BOUNDARY_PADDING,
+            top: height - BOUNDARY_PADDING,
+            shape: BoundaryShape::Rect,
+        }
+    }
+
+    // The largest circle that fits inside the rectangle.
+    fn inscribed_circle(&self) -> BoundaryShape {
+        let center = Vec2::new(self.left + self.right, self.bottom + self.top) * 0.5;
+        let radius = (self.right - self.left).min(self.top - self.bottom) * 0.5;
+        BoundaryShape::Circle { center, radius }
+    }
+
+    // Rectangle -> circle -> rectangle.
+    pub fn cycle_shape(&mut self) {
+        self.shape = match self.shape {
+            BoundaryShape::Rect => self.inscribed_circle(),
+            BoundaryShape::Circle { .. } => BoundaryShape::Rect,
+        };
+    }
+
+    // The same kind of container as `self`, refitted to this boundary's rectangle.
+    pub fn with_shape_of(mut self, other: &Boundary) -> Self {
+        if let BoundaryShape::Circle { .. } = other.shape {
+            self.shape = self.inscribed_circle();
+        }
+        self
+    }
+
+    // Keeps a circle of the given radius fully inside the walls.
+    pub fn clamp(&self, position: Vec2, radius: f32) -> Vec2 {
+        let position = Vec2::new(
+            position.x.clamp(self.left + radius, self.right - radius),
+            position.y.clamp(self.bottom + radius, self.top - radius),
+        );
+        match self.shape {
+            BoundaryShape::Rect => position,
+            BoundaryShape::Circle {
+                center,
+                radius: container,
+            } => center + (position - center).clamp_length_max((container - radius).max(0.0)),
+        }
+    }
+
+    pub fn draw(&self) {
+        if let BoundaryShape::Circle { center, radius } = self.shape {
+            let screen_center = world_to_screen(center);
+            let screen_radius = radius * pixels_per_meter();
+            draw_circle_lines(screen_center.x, screen_center.y, screen_radius, 2.0, WHITE);
+            return;
+        }
+
+        let corners = [
+            world_to_screen(Vec2::new(self.left, self.top)),
+            world_to_screen(Vec2::new(self.right, self.top)),
+            world_to_screen(Vec2::new(self.right, self.bottom)),
+            world_to_screen(Vec2::new(self.left, self.bottom)),
+        ];
+
+        for i in 0..4 {
+            let next = (i + 1) % 4;
+            draw_line(
+                corners[i].x,
+                corners[i].y,
+                corners[next].x,
+                corners[next].y,
+                2.0,
+                WHITE,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coords::{SIM_MIN_WIDTH, WorldConfig, set_world};
+
+    #[test]
+    fn extents_stay_fixed_across_resizes() {
+        // Only the first call counts, so the coords and world tests fix the
+        // world from this same window
+        set_world(WorldConfig::for_window(
+            Vec2::new(800.0, 600.0),
+            SIM_MIN_WIDTH,
+        ));
+        let at_startup = Boundary::new();
+        assert_eq!(at_startup.top, SIM_MIN_WIDTH - BOUNDARY_PADDING);
+
+        // Each resize asks for a world fitted to the new window; the one
+        // fixed at startup stays
+        for window in [(1920.0, 1080.0), (400.0, 900.0), (801.0, 600.0)] {
+            set_world(WorldConfig::for_window(Vec2::from(window), SIM_MIN_WIDTH));
+            let resized = Boundary::new();
+            assert_eq!(
+                (resized.left, resized.right, resized.bottom, resized.top),
+                (
+                    at_startup.left,
+                    at_startup.right,
+                    at_startup.bottom,
+                    at_startup.top
+                ),
+                "boundary changed for a {window:?} window"
+            );
+        }
+    }
+}
@@ -0,0 +1,411 @@
+use macroquad::prelude::*;
+use std::collections::HashMap;
+
+use crate::particle::Particle;
+
+// === Broad Phase ===
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    particle_cells: Vec<(i32, i32)>,
+}
+
+impl SpatialHash {
+    pub fn new() -> Self {
+        Self {
+            cell_size: 1.0,
+            cells: HashMap::new(),
+            particle_cells: Vec::new(),
+        }
+    }
+
+    // Cells are one particle diameter wide, so any two touching particles
+    // always sit in the same or in neighbouring cells. `margin` widens them
+    // further, e.g. by how far particles may have moved for swept checks.
+    pub fn rebuild(&mut self, particles: &[Particle], margin: f32) {
+        let max_radius = particles.iter().map(|p| p.radius()).fold(0.0, f32::max);
+        self.cell_size = (max_radius * 2.0 + margin).max(f32::EPSILON);
+
+        // Keep last step's buckets to reuse their allocations, but drop the
+        // ones nobody occupied so the map doesn't grow with every cell visited.
+        self.cells.retain(|_, bucket| !bucket.is_empty());
+        for bucket in self.cells.values_mut() {
+            bucket.clear();
+        }
+        self.particle_cells.clear();
+
+        for (i, p) in particles.iter().enumerate() {
+            let cell = self.cell_of(p.position);
+            self.cells.entry(cell).or_default().push(i);
+            self.particle_cells.push(cell);
+        }
+    }
+
+    // Particles indexed by the last rebuild.
+    pub fn len(&self) -> usize {
+        self.particle_cells.len()
+    }
+
+    fn cell_of(&self, position: Vec2) -> (i32, i32) {
+        let cell = (position / self.cell_size).floor();
+        (cell.x as i32, cell.y as i32)
+    }
+
+    // Every pair (i, j) with i < j in the 3x3 block of cells around each
+    // other, each reported exactly once, appended to `pairs`.
+    #[cfg(not(feature = "parallel"))]
+    pub fn candidate_pairs_into(&self, pairs: &mut Vec<(usize, usize)>) {
+        pairs.extend(
+            self.particle_cells
+                .iter()
+                .enumerate()
+                .flat_map(|(i, &cell)| self.pairs_from(i, cell)),
+        );
+    }
+
+    // Each particle's neighbours are looked up independently, so the work
+    // splits across threads; extending keeps the serial order.
+    #[cfg(feature = "parallel")]
+    pub fn candidate_pairs_into(&self, pairs: &mut Vec<(usize, usize)>) {
+        use rayon::prelude::*;
+
+        pairs.par_extend(
+            self.particle_cells
+                .par_iter()
+                .enumerate()
+                .flat_map_iter(|(i, &cell)| self.pairs_from(i, cell)),
+        );
+    }
+
+    // Cells a query of `radius` around `center` has to look through.
+    pub fn cells_within(&self, center: Vec2, radius: f32) -> usize {
+        let (min_x, min_y) = self.cell_of(center - Vec2::splat(radius));
+        let (max_x, max_y) = self.cell_of(center + Vec2::splat(radius));
+        let span = |min: i32, max: i32| (i64::from(max) - i64::from(min) + 1) as usize;
+        span(min_x, max_x).saturating_mul(span(min_y, max_y))
+    }
+
+    // Indices, in order, of the particles whose centers are within `radius`
+    // of `center`, edge included. `particles` must be the ones the hash was
+    // last built from, in the same places.
+    pub fn query_radius(&self, particles: &[Particle], center: Vec2, radius: f32) -> Vec<usize> {
+        let (min_x, min_y) = self.cell_of(center - Vec2::splat(radius));
+        let (max_x, max_y) = self.cell_of(center + Vec2::splat(radius));
+        let mut found = Vec::new();
+        for cy in min_y..=max_y {
+            for cx in min_x..=max_x {
+                if let Some(bucket) = self.cells.get(&(cx, cy)) {
+                    found.extend(bucket.iter().copied().filter(|&i| {
+                        particles[i].position.distance_squared(center) <= radius * radius
+                    }));
+                }
+            }
+        }
+        found.sort_unstable();
+        found
+    }
+
+    // Pairs (i, j) with j > i for particle i in `cell`.
+    fn pairs_from(&self, i: usize, (cx, cy): (i32, i32)) -> impl Iterator<Item = (usize, usize)> {
+        (-1..=1)
+            .flat_map(move |dy| (-1..=1).map(move |dx| (cx + dx, cy + dy)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flat_map(move |bucket| bucket.iter().filter(move |&&j| j > i).map(move |&j| (i, j)))
+    }
+}
+
+// === Quadtree ===
+pub const QUAD_TREE_CAPACITY: usize = 8; // Items a node holds before it splits
+pub const QUAD_TREE_MAX_DEPTH: u32 = 8;
+
+// Region tree over axis-aligned boxes. Each box lives in the deepest node that
+// fully contains it, so big particles sit high up and small ones sink down,
+// which suits scenes with very mixed sizes better than one grid cell size.
+pub struct QuadTree {
+    min: Vec2,
+    max: Vec2,
+    depth: u32,
+    items: Vec<(usize, Vec2, Vec2)>, // Index with its box's min and max corners
+    children: Vec<QuadTree>,         // Empty, or the four quadrants
+}
+
+impl QuadTree {
+    pub fn new(min: Vec2, max: Vec2) -> Self {
+        Self::with_depth(min, max, 0)
+    }
+
+    fn with_depth(min: Vec2, max: Vec2, depth: u32) -> Self {
+        Self {
+            min,
+            max,
+            depth,
+            items: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    // Tree over every particle's bounding box, grown by `margin` overall like
+    // SpatialHash::rebuild.
+    pub fn from_particles(particles: &[Particle], margin: f32) -> Self {
+        let bounds = |p: &Particle| {
+            let reach = Vec2::splat(p.radius() + margin * 0.5);
+            (p.position - reach, p.position + reach)
+        };
+        let (min, max) = particles
+            .iter()
+            .map(bounds)
+            .reduce(|(a_min, a_max), (b_min, b_max)| (a_min.min(b_min), a_max.max(b_max)))
+            .unwrap_or((Vec2::ZERO, Vec2::ONE));
+
+        let mut tree = Self::new(min, max);
+        for (i, p) in particles.iter().enumerate() {
+            let (min, max) = bounds(p);
+            tree.insert(i, min, max);
+        }
+        tree
+    }
+
+    pub fn insert(&mut self, index: usize, min: Vec2, max: Vec2) {
+        if let Some(child) = self.child_containing(min, max) {
+            self.children[child].insert(index, min, max);
+            return;
+        }
+        self.items.push((index, min, max));
+
+        if self.children.is_empty()
+            && self.items.len() > QUAD_TREE_CAPACITY
+            && self.depth < QUAD_TREE_MAX_DEPTH
+        {
+            self.subdivide();
+        }
+    }
+
+    fn subdivide(&mut self) {
+        let mid = (self.min + self.max) * 0.5;
+        let depth = self.depth + 1;
+        self.children = vec![
+            Self::with_depth(self.min, mid, depth),
+            Self::with_depth(
+                Vec2::new(mid.x, self.min.y),
+                Vec2::new(self.max.x, mid.y),
+                depth,
+            ),
+            Self::with_depth(
+                Vec2::new(self.min.x, mid.y),
+                Vec2::new(mid.x, self.max.y),
+                depth,
+            ),
+            Self::with_depth(mid, self.max, depth),
+        ];
+
+        // Push down whatever now fits inside a single quadrant
+        for (index, min, max) in std::mem::take(&mut self.items) {
+            self.insert(index, min, max);
+        }
+    }
+
+    fn child_containing(&self, min: Vec2, max: Vec2) -> Option<usize> {
+        self.children
+            .iter()
+            .position(|c| min.cmpge(c.min).all() && max.cmple(c.max).all())
+    }
+
+    // Indices of every stored box overlapping the given one.
+    pub fn query_range(&self, min: Vec2, max: Vec2) -> Vec<usize> {
+        let mut found = Vec::new();
+        self.query_into(min, max, &mut found);
+        found
+    }
+
+    fn query_into(&self, min: Vec2, max: Vec2, found: &mut Vec<usize>) {
+        if !overlaps(min, max, self.min, self.max) {
+            return;
+        }
+        found.extend(
+            self.items
+                .iter()
+                .filter(|(_, item_min, item_max)| overlaps(min, max, *item_min, *item_max))
+                .map(|(index, _, _)| *index),
+        );
+        for child in &self.children {
+            child.query_into(min, max, found);
+        }
+    }
+
+    // Same contract as SpatialHash::candidate_pairs_into, for the particles
+    // the tree was built from.
+    pub fn candidate_pairs_into(
+        &self,
+        particles: &[Particle],
+        margin: f32,
+        pairs: &mut Vec<(usize, usize)>,
+    ) {
+        for (i, p) in particles.iter().enumerate() {
+            let reach = Vec2::splat(p.radius() + margin * 0.5);
+            let mut others = self.query_range(p.position - reach, p.position + reach);
+            others.retain(|&j| j > i);
+            others.sort_unstable(); // Fixed order keeps runs deterministic
+            pairs.extend(others.into_iter().map(|j| (i, j)));
+        }
+    }
+}
+
+fn overlaps(a_min: Vec2, a_max: Vec2, b_min: Vec2, b_max: Vec2) -> bool {
+    a_min.cmple(b_max).all() && b_min.cmple(a_max).all()
+}
+
+// === Broad Phase Selection ===
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BroadPhase {
+    Grid,     // SpatialHash
+    QuadTree, // QuadTree
+}
+
+impl BroadPhase {
+    pub fn next(self) -> Self {
+        match self {
+            BroadPhase::Grid => BroadPhase::QuadTree,
+            BroadPhase::QuadTree => BroadPhase::Grid,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spawner::{SPAWN_SEED, Spawner};
+    use std::ops::Range;
+
+    // `count` circles of the given radii strewn at random over a square
+    // `size` wide, free to overlap.
+    fn random_scene(count: usize, size: f32, radius: Range<f32>) -> Vec<Particle> {
+        let mut spawner = Spawner::new(SPAWN_SEED);
+        (0..count)
+            .map(|_| {
+                let position = Vec2::new(spawner.range(0.0..size), spawner.range(0.0..size));
+                spawner.particle(position, Vec2::ZERO, radius.clone())
+            })
+            .collect()
+    }
+
+    fn touching(particles: &[Particle], (i, j): (usize, usize)) -> bool {
+        let (a, b) = (&particles[i], &particles[j]);
+        a.position.distance(b.position) <= a.radius() + b.radius()
+    }
+
+    // Every touching pair, found by checking all of them.
+    fn brute_force(particles: &[Particle]) -> Vec<(usize, usize)> {
+        (0..particles.len())
+            .flat_map(|i| (i + 1..particles.len()).map(move |j| (i, j)))
+            .filter(|&pair| touching(particles, pair))
+            .collect()
+    }
+
+    fn sorted_hits(particles: &[Particle], pairs: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+        let mut hits: Vec<_> = pairs
+            .into_iter()
+            .filter(|&pair| touching(particles, pair))
+            .collect();
+        hits.sort_unstable();
+        hits
+    }
+
+    #[test]
+    fn grid_finds_the_same_collisions_as_brute_force() {
+        let particles = random_scene(500, 40.0, 0.2..0.6);
+        let mut grid = SpatialHash::new();
+        grid.rebuild(&particles, 0.0);
+
+        let expected = brute_force(&particles);
+        assert!(expected.len() > 50, "only {} collisions", expected.len());
+        let mut pairs = Vec::new();
+        grid.candidate_pairs_into(&mut pairs);
+        assert_eq!(sorted_hits(&particles, pairs), expected);
+    }
+
+    #[test]
+    fn grid_catches_pairs_across_cell_boundaries() {
+        let mut particles = random_scene(40, 10.0, 0.1..0.4);
+        // With 0.5 m the largest radius the cells are 1 m wide, so each of
+        // these pairs straddles a cell edge or corner.
+        for (a, b) in [
+            (Vec2::new(2.9, 12.5), Vec2::new(3.1, 12.5)),
+            (Vec2::new(14.5, 6.95), Vec2::new(14.5, 7.05)),
+            (Vec2::new(11.95, 11.95), Vec2::new(12.05, 12.05)),
+            (Vec2::new(16.02, 3.98), Vec2::new(15.98, 4.02)),
+        ] {
+            particles.push(Particle::new(a, Vec2::ZERO, 0.5, 1.0, WHITE));
+            particles.push(Particle::new(b, Vec2::ZERO, 0.5, 1.0, WHITE));
+        }
+        let mut grid = SpatialHash::new();
+        grid.rebuild(&particles, 0.0);
+
+        let expected = brute_force(&particles);
+        for k in 0..4 {
+            let pair = (40 + 2 * k, 41 + 2 * k);
+            assert!(expected.contains(&pair), "{pair:?} not touching");
+        }
+        let mut pairs = Vec::new();
+        grid.candidate_pairs_into(&mut pairs);
+        assert_eq!(sorted_hits(&particles, pairs), expected);
+    }
+
+    #[test]
+    fn quadtree_candidates_cover_brute_force() {
+        // Sizes spread wide enough that some particles stay high in the tree
+        let particles = random_scene(300, 30.0, 0.05..2.0);
+        let tree = QuadTree::from_particles(&particles, 0.0);
+        let mut candidates = Vec::new();
+        tree.candidate_pairs_into(&particles, 0.0, &mut candidates);
+
+        let expected = brute_force(&particles);
+        assert!(expected.len() > 50, "only {} collisions", expected.len());
+        for pair in &expected {
+            assert!(candidates.contains(pair), "quadtree missed {pair:?}");
+        }
+        assert_eq!(sorted_hits(&particles, candidates), expected);
+    }
+
+    #[test]
+    fn quadtree_query_range_finds_every_overlapping_box() {
+        let particles = random_scene(200, 20.0, 0.05..1.5);
+        let tree = QuadTree::from_particles(&particles, 0.0);
+        let (min, max) = (Vec2::new(5.0, 5.0), Vec2::new(9.0, 12.0));
+
+        let mut found = tree.query_range(min, max);
+        found.sort_unstable();
+        let expected: Vec<usize> = (0..particles.len())
+            .filter(|&i| {
+                let reach = Vec2::splat(particles[i].radius());
+                overlaps(
+                    min,
+                    max,
+                    particles[i].position - reach,
+                    particles[i].position + reach,
+                )
+            })
+            .collect();
+        assert_eq!(found, expected);
+    }
+
+    // Around (10, 10) with radius 5: one at the center, two exactly 5 m out
+    // and one just past it.
+    fn ring_scene() -> Vec<Particle> {
+        [(10.0, 10.0), (13.0, 14.0), (10.0, 5.0), (15.001, 10.0)]
+            .into_iter()
+            .map(|(x, y)| Particle::new(Vec2::new(x, y), Vec2::ZERO, 0.5, 1.0, WHITE))
+            .collect()
+    }
+
+    #[test]
+    fn query_radius_includes_particles_exactly_at_the_radius() {
+        let particles = ring_scene();
+        let mut grid = SpatialHash::new();
+        grid.rebuild(&particles, 0.0);
+
+        let center = Vec2::new(10.0, 10.0);
+        assert_eq!(grid.query_radius(&particles, center, 5.0), vec![0, 1, 2]);
+        assert_eq!(grid.query_radius(&particles, center, 4.999), vec![0]);
+        assert_eq!(grid.query_radius(&particles, center, 0.0), vec![0]);
+    }
+}
@@ -0,0 +1,124 @@
+use macroquad::math::Vec2;
+use std::str::FromStr;
+
+use crate::config::Config;
+use crate::physics::Integrator;
+
+pub const USAGE: &str = "\
+usage: cargo run -- [options]
+
+  --headless N          step N times without a window and print the final state
+  --assert-energy N     check energy conservation over N steps of a lossless scene
+  --scene PATH          build the startup scene from a JSON or TOML scene file
+  --record PATH         log every particle's position and velocity after each step to CSV
+  --seed N              seed for every random choice (printed at startup)
+  --count N             start with N randomly placed particles instead of the demo ones
+  --gravity G|X,Y       gravity (m/s²), straight down/up or as a vector
+  --restitution E       bounciness, 0..=1
+  --friction F          wall-parallel velocity kept per step on a wall, 0..=1
+  --integrator NAME     euler, verlet or position-verlet
+  --ccd                 continuous collision detection
+  --help                show this message";
+
+// === Command Line ===
+// `--headless N` steps N times without a window, and `--assert-energy N`
+// checks energy conservation over N steps of a lossless scene. `--scene PATH`
+// loads the startup scene from a file, `--record PATH` logs trajectories to
+// CSV, `--seed N` seeds the spawner, `--count N`
+// scatters N random particles, and `--gravity`, `--restitution` and
+// `--friction` override the configured physics. `--integrator NAME`
+// picks the integrator and `--ccd` turns on continuous collisions. Anything
+// unrecognised or unparseable is an error rather than silently ignored.
+#[derive(Default)]
+pub struct Options {
+    pub help: bool,
+    pub headless_steps: Option<usize>,
+    pub assert_energy_steps: Option<usize>,
+    pub scene: Option<String>,
+    pub record_path: Option<String>,
+    pub seed: Option<u64>,
+    pub count: Option<usize>,
+    pub gravity: Option<Vec2>,
+    pub restitution: Option<f32>,
+    pub friction: Option<f32>,
+    pub integrator: Option<Integrator>,
+    pub ccd: bool,
+}
+
+impl Options {
+    pub fn from_args() -> Result<Self, String> {
+        Self::parse(std::env::args().skip(1))
+    }
+
+    fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut options = Self::default();
+        let mut args = args.into_iter();
+        while let Some(flag) = args.next() {
+            let mut value = || args.next().ok_or(format!("{flag} needs a value"));
+            match flag.as_str() {
+                "--help" | "-h" => options.help = true,
+                "--headless" => options.headless_steps = Some(parse(&flag, value()?)?),
+                "--assert-energy" => options.assert_energy_steps = Some(parse(&flag, value()?)?),
+                "--scene" => options.scene = Some(value()?),
+                "--record" => options.record_path = Some(value()?),
+                "--seed" => options.seed = Some(parse(&flag, value()?)?),
+                "--count" => options.count = Some(parse(&flag, value()?)?),
+                "--gravity" => options.gravity = Some(parse_gravity(&flag, value()?)?),
+                "--restitution" => options.restitution = Some(parse(&flag, value()?)?),
+                "--friction" => options.friction = Some(parse(&flag, value()?)?),
+                "--integrator" => {
+                    let name = value()?;
+                    let integrator = Integrator::from_name(&name)
+                        .ok_or(format!("--integrator: unknown integrator '{name}'"))?;
+                    options.integrator = Some(integrator);
+                }
+                "--ccd" => options.ccd = true,
+                _ => return Err(format!("unknown option '{flag}'")),
+            }
+        }
+        Ok(options)
+    }
+
+    // Overrides the given settings with whatever was passed on the command line.
+    pub fn apply(&self, config: &mut Config) {
+        let physics = &mut config.physics;
+        if let Some(gravity) = self.gravity {
+            physics.gravity = gravity;
+        }
+        if let Some(restitution) = self.restitution {
+            physics.restitution = restitution;
+        }
+        if let Some(friction) = self.friction {
+            physics.friction = friction;
+        }
+        if let Some(integrator) = self.integrator {
+            physics.integrator = integrator;
+        }
+        if self.ccd {
+            physics.ccd = true;
+        }
+        if let Some(seed) = self.seed {
+            config.seed = seed;
+        }
+        if let Some(count) = self.count {
+            config.random_particles = count;
+        }
+    }
+}
+
+fn parse<T: FromStr>(flag: &str, value: String) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{flag}: invalid value '{value}'"))
+}
+
+// A single number is vertical gravity, as before; "x,y" gives the full vector.
+fn parse_gravity(flag: &str, value: String) -> Result<Vec2, String> {
+    match value.split_once(',') {
+        Some((x, y)) => Ok(Vec2::new(
+            parse(flag, x.trim().to_string())?,
+            parse(flag, y.trim().to_string())?,
+        )),
+        None => Ok(Vec2::new(0.0, parse(flag, value)?)),
+    }
+}
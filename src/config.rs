@@ -0,0 +1,155 @@
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::io;
+
+use crate::coords::SIM_MIN_WIDTH;
+use crate::emitter::MAX_PARTICLES;
+use crate::particle::Particle;
+use crate::physics::Physics;
+use crate::spawner::SPAWN_SEED;
+use crate::{MAX_STEPS_PER_FRAME, TIME_STEP};
+
+pub const CONFIG_PATH: &str = "config.toml";
+
+// === Config ===
+// Startup settings read from `config.toml`. Every field is optional; anything
+// left out keeps the built-in value, and a non-empty `particles` list replaces
+// the demo scene's particles.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub time_step: f32,           // Fixed time step (s) before splitting into substeps
+    pub substeps: u32,            // Physics steps per time step, each time_step / substeps long
+    pub max_steps_per_frame: u32, // Accumulated time beyond this many steps in a frame is dropped
+    pub sim_min_width: f32,       // World width (m) across the narrower side of the starting window
+    pub max_particles: usize,     // Emitters stop once the world holds this many
+    pub random_particles: usize,  // Scatter this many random particles in place of the demo ones
+    pub seed: u64,                // Seeds every random choice; the same seed replays the same run
+    pub physics: Physics,
+    pub particles: Vec<Particle>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            time_step: TIME_STEP,
+            substeps: 1,
+            max_steps_per_frame: MAX_STEPS_PER_FRAME,
+            sim_min_width: SIM_MIN_WIDTH,
+            max_particles: MAX_PARTICLES,
+            random_particles: 0,
+            seed: SPAWN_SEED,
+            physics: Physics::default(),
+            particles: Vec::new(),
+        }
+    }
+}
+
+pub enum ConfigError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(err) => write!(f, "could not read {CONFIG_PATH}: {err}"),
+            ConfigError::Parse(err) => write!(f, "could not parse {CONFIG_PATH}: {err}"),
+            ConfigError::Invalid(reason) => write!(f, "invalid {CONFIG_PATH}: {reason}"),
+        }
+    }
+}
+
+impl Config {
+    // Reads and validates the config, or the defaults if the file doesn't exist.
+    pub fn load(path: &str) -> Result<Self, ConfigError> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(ConfigError::Io(err)),
+        };
+        let config: Self = toml::from_str(&text).map_err(ConfigError::Parse)?;
+        config.validate().map_err(ConfigError::Invalid)?;
+        Ok(config)
+    }
+
+    // Called by load, and again after command-line overrides.
+    pub fn validate(&self) -> Result<(), String> {
+        let physics = &self.physics;
+        check(self.time_step > 0.0, "time_step must be positive")?;
+        check(self.substeps >= 1, "substeps must be at least 1")?;
+        check(
+            self.max_steps_per_frame >= 1,
+            "max_steps_per_frame must be at least 1",
+        )?;
+        check(self.sim_min_width > 0.0, "sim_min_width must be positive")?;
+        check(
+            (0.0..=1.0).contains(&physics.restitution),
+            "physics.restitution must be in 0..=1",
+        )?;
+        check(
+            physics
+                .boundary_restitution
+                .is_none_or(|r| (0.0..=1.0).contains(&r)),
+            "physics.boundary_restitution must be in 0..=1",
+        )?;
+        check(
+            (0.0..=1.0).contains(&physics.friction),
+            "physics.friction must be in 0..=1",
+        )?;
+        check(
+            physics.contact_friction >= 0.0,
+            "physics.contact_friction must not be negative",
+        )?;
+        check(
+            physics.drag_coefficient >= 0.0 && physics.drag >= 0.0,
+            "physics.drag_coefficient and physics.drag must not be negative",
+        )?;
+        check(
+            physics.correction_slop >= 0.0,
+            "physics.correction_slop must not be negative",
+        )?;
+        check(
+            (0.0..=1.0).contains(&physics.correction_percent),
+            "physics.correction_percent must be in 0..=1",
+        )?;
+        check(
+            (0.0..=1.0).contains(&physics.baumgarte),
+            "physics.baumgarte must be in 0..=1",
+        )?;
+        check(
+            physics.solver_iterations >= 1,
+            "physics.solver_iterations must be at least 1",
+        )?;
+        check(
+            physics.velocity_threshold >= 0.0,
+            "physics.velocity_threshold must not be negative",
+        )?;
+
+        for (i, p) in self.particles.iter().enumerate() {
+            check(
+                p.mass > 0.0,
+                &format!("particles[{i}].mass must be positive"),
+            )?;
+            check(
+                p.radius() > 0.0,
+                &format!("particles[{i}] must have a positive size"),
+            )?;
+            check(
+                p.restitution.is_none_or(|r| (0.0..=1.0).contains(&r)),
+                &format!("particles[{i}].restitution must be in 0..=1"),
+            )?;
+            check(
+                p.friction.is_none_or(|f| (0.0..=1.0).contains(&f)),
+                &format!("particles[{i}].friction must be in 0..=1"),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn check(ok: bool, reason: &str) -> Result<(), String> {
+    if ok { Ok(()) } else { Err(reason.to_string()) }
+}
@@ -0,0 +1,204 @@
+use macroquad::prelude::*;
+use std::cell::Cell;
+use std::sync::OnceLock;
+
+pub const SIM_MIN_WIDTH: f32 = 20.0;
+
+// === World Size ===
+// The physics domain, fixed at startup. Resizing the window afterwards only
+// rescales the view; the boundary and everything in it stay put.
+#[derive(Clone, Copy)]
+pub struct WorldConfig {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl WorldConfig {
+    // Sized so `min_width` meters span the narrower side of a window this many pixels big.
+    pub fn for_window(window: Vec2, min_width: f32) -> Self {
+        let ppm = window.x.min(window.y) / min_width;
+        Self {
+            width: window.x / ppm,
+            height: window.y / ppm,
+        }
+    }
+}
+
+static WORLD: OnceLock<WorldConfig> = OnceLock::new();
+
+// Fixes the world size for this run. Only the first call has any effect.
+pub fn set_world(world: WorldConfig) {
+    let _ = WORLD.set(world);
+}
+
+pub fn world_dimensions() -> Vec2 {
+    let world = WORLD
+        .get()
+        .copied()
+        .unwrap_or_else(|| WorldConfig::for_window(screen_size(), SIM_MIN_WIDTH));
+    Vec2::new(world.width, world.height)
+}
+
+fn screen_size() -> Vec2 {
+    Vec2::new(screen_width(), screen_height())
+}
+
+// Largest scale at which the whole world still fits in a window of the given
+// size, before zooming.
+fn fit_pixels_per_meter(screen: Vec2) -> f32 {
+    let world = world_dimensions();
+    (screen.x / world.x).min(screen.y / world.y)
+}
+
+// Pixels left over on each side when the window's shape doesn't match the
+// world's, so the unzoomed world sits centered.
+fn letterbox(screen: Vec2) -> Vec2 {
+    (screen - world_dimensions() * fit_pixels_per_meter(screen)) * 0.5
+}
+
+// === Camera ===
+pub const MIN_ZOOM: f32 = 0.25;
+pub const MAX_ZOOM: f32 = 8.0;
+
+// Zoom multiplies the fitted scale; pan shifts which world point sits at the
+// view's origin. All the conversions below go through the current camera.
+#[derive(Clone, Copy)]
+pub struct Camera {
+    pub zoom: f32,
+    pub pan: Vec2, // Meters
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            zoom: 1.0,
+            pan: Vec2::ZERO,
+        }
+    }
+}
+
+impl Camera {
+    pub fn pixels_per_meter(&self) -> f32 {
+        self.pixels_per_meter_in(screen_size())
+    }
+
+    pub fn world_to_screen(&self, world_pos: Vec2) -> Vec2 {
+        self.world_to_screen_in(world_pos, screen_size())
+    }
+
+    // Exact inverse of world_to_screen.
+    pub fn screen_to_world(&self, screen_pos: Vec2) -> Vec2 {
+        self.screen_to_world_in(screen_pos, screen_size())
+    }
+
+    // The three above, for a window of the given size rather than the
+    // current one.
+    fn pixels_per_meter_in(&self, screen: Vec2) -> f32 {
+        fit_pixels_per_meter(screen) * self.zoom
+    }
+
+    fn world_to_screen_in(&self, world_pos: Vec2, screen: Vec2) -> Vec2 {
+        let ppm = self.pixels_per_meter_in(screen);
+        let offset = letterbox(screen);
+        let view = world_pos - self.pan;
+        Vec2::new(offset.x + view.x * ppm, screen.y - offset.y - view.y * ppm)
+    }
+
+    fn screen_to_world_in(&self, screen_pos: Vec2, screen: Vec2) -> Vec2 {
+        let ppm = self.pixels_per_meter_in(screen);
+        let offset = letterbox(screen);
+        let view = Vec2::new(
+            (screen_pos.x - offset.x) / ppm,
+            (screen.y - offset.y - screen_pos.y) / ppm,
+        );
+        view + self.pan
+    }
+
+    // Zooms by `factor`, keeping the world point under `screen_pos` in place.
+    pub fn zoom_at(&mut self, screen_pos: Vec2, factor: f32) {
+        let before = self.screen_to_world(screen_pos);
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.pan += before - self.screen_to_world(screen_pos);
+    }
+
+    // Drags the view along with the mouse by a screen-space offset.
+    pub fn pan_by(&mut self, screen_delta: Vec2) {
+        self.pan -= Vec2::new(screen_delta.x, -screen_delta.y) / self.pixels_per_meter();
+    }
+}
+
+thread_local! {
+    static CAMERA: Cell<Camera> = Cell::new(Camera::default());
+}
+
+pub fn camera() -> Camera {
+    CAMERA.with(Cell::get)
+}
+
+pub fn set_camera(camera: Camera) {
+    CAMERA.with(|cell| cell.set(camera));
+}
+
+// === Coordinate Conversion ===
+pub fn pixels_per_meter() -> f32 {
+    camera().pixels_per_meter()
+}
+
+pub fn screen_to_world(screen_pos: Vec2) -> Vec2 {
+    camera().screen_to_world(screen_pos)
+}
+
+pub fn world_to_screen(world_pos: Vec2) -> Vec2 {
+    camera().world_to_screen(world_pos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::boundary::Boundary;
+
+    #[test]
+    fn resizing_the_window_keeps_the_world_in_meters() {
+        // Every test that fixes the world uses this window, as only the
+        // first call takes effect
+        set_world(WorldConfig::for_window(
+            Vec2::new(800.0, 600.0),
+            SIM_MIN_WIDTH,
+        ));
+        let boundary = Boundary::new();
+        let particle = Vec2::new(5.0, 3.0);
+        let camera = Camera::default();
+
+        // The walls' extents measured on screen and turned back into meters,
+        // and where the particle sits between them
+        let seen_in = |screen: Vec2| {
+            let ppm = camera.pixels_per_meter_in(screen);
+            let bottom_left =
+                camera.world_to_screen_in(Vec2::new(boundary.left, boundary.bottom), screen);
+            let top_right =
+                camera.world_to_screen_in(Vec2::new(boundary.right, boundary.top), screen);
+            let at = camera.world_to_screen_in(particle, screen);
+            let extents = Vec2::new(top_right.x - bottom_left.x, bottom_left.y - top_right.y) / ppm;
+            let between = (at - bottom_left) / (top_right - bottom_left);
+            (ppm, extents, between)
+        };
+        let (small_ppm, small_extents, small_between) = seen_in(Vec2::new(800.0, 600.0));
+        let (large_ppm, large_extents, large_between) = seen_in(Vec2::new(1920.0, 1080.0));
+
+        assert!(large_ppm > small_ppm, "the bigger window didn't scale up");
+        let walls = Vec2::new(
+            boundary.right - boundary.left,
+            boundary.top - boundary.bottom,
+        );
+        for extents in [small_extents, large_extents] {
+            assert!(
+                extents.abs_diff_eq(walls, 1e-4),
+                "walls {extents} m apart, not {walls}"
+            );
+        }
+        assert!(
+            small_between.abs_diff_eq(large_between, 1e-5),
+            "particle moved from {small_between} to {large_between} of the way between the walls"
+        );
+    }
+}
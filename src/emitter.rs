@@ -0,0 +1,82 @@
+use macroquad::prelude::*;
+use std::ops::Range;
+
+use crate::coords::{pixels_per_meter, world_to_screen};
+use crate::particle::Particle;
+use crate::spawner::Spawner;
+
+pub const MAX_PARTICLES: usize = 400; // Default cap on the world's particle count
+
+// === Emitter ===
+// A fountain: sprays `rate` particles per second at `speed`, spread evenly
+// within `spread_angle` radians around `direction`, with radii drawn from
+// `radius` and mass following area. Stops emitting while the world already
+// holds `max_particles`.
+pub struct Emitter {
+    pub position: Vec2,
+    pub direction: Vec2, // Unit vector the spray is centered on
+    pub rate: f32,
+    pub spread_angle: f32,
+    pub speed: f32,
+    pub radius: Range<f32>,
+    pub active: bool,
+    pub max_particles: usize,
+    pub lifetime: Option<f32>, // Given to every emitted particle
+    backlog: f32,              // Fraction of a particle owed from previous steps
+}
+
+impl Emitter {
+    pub fn new(position: Vec2, direction: Vec2, rate: f32, spread_angle: f32, speed: f32) -> Self {
+        Self {
+            position,
+            direction: direction.normalize_or(Vec2::NEG_Y),
+            rate,
+            spread_angle,
+            speed,
+            radius: 0.15..0.3,
+            active: false,
+            max_particles: MAX_PARTICLES,
+            lifetime: None,
+            backlog: 0.0,
+        }
+    }
+
+    // Adds however many particles `dt` seconds of emission are worth.
+    pub fn emit(&mut self, particles: &mut Vec<Particle>, spawner: &mut Spawner, dt: f32) {
+        if !self.active {
+            self.backlog = 0.0;
+            return;
+        }
+
+        self.backlog += self.rate * dt;
+        while self.backlog >= 1.0 {
+            if particles.len() >= self.max_particles {
+                self.backlog = 0.0;
+                return;
+            }
+            self.backlog -= 1.0;
+
+            let half_spread = self.spread_angle * 0.5;
+            let angle = self.direction.to_angle() + spawner.range(-half_spread..half_spread);
+            let velocity = Vec2::from_angle(angle) * self.speed;
+            let mut particle = spawner.particle(self.position, velocity, self.radius.clone());
+            particle.lifetime = self.lifetime;
+            particles.push(particle);
+        }
+    }
+
+    // Nozzle marker, lit while the emitter is running.
+    pub fn draw(&self) {
+        let screen_pos = world_to_screen(self.position);
+        let nozzle = world_to_screen(self.position + self.direction * 0.6);
+        let color = if self.active { YELLOW } else { GRAY };
+        draw_circle_lines(
+            screen_pos.x,
+            screen_pos.y,
+            0.3 * pixels_per_meter(),
+            2.0,
+            color,
+        );
+        draw_line(screen_pos.x, screen_pos.y, nozzle.x, nozzle.y, 2.0, color);
+    }
+}
@@ -0,0 +1,83 @@
+use macroquad::prelude::*;
+
+use crate::particle::Particle;
+
+// === Forces ===
+// Something that pushes on each particle on its own account. `apply` returns
+// the acceleration (m/s²) it gives `particle`, with every other particle in
+// `particles` to consult; the world sums the results of all its forces before
+// the integrator runs. Pairwise effects that act back on both particles, like
+// springs and contacts, stay outside this.
+pub trait Force {
+    fn apply(&self, particle: &Particle, particles: &[Particle]) -> Vec2;
+}
+
+// The same acceleration everywhere, whatever the mass: gravity, or wind,
+// which the world also applies as one of these.
+pub struct Gravity {
+    pub acceleration: Vec2,
+}
+
+impl Force for Gravity {
+    fn apply(&self, _particle: &Particle, _particles: &[Particle]) -> Vec2 {
+        self.acceleration
+    }
+}
+
+// Air resistance against the particle's velocity: quadratic F = -k·|v|·v,
+// which heavy particles shrug off better, plus linear a = -c·v.
+pub struct Drag {
+    pub quadratic: f32, // k (kg/m)
+    pub linear: f32,    // c (1/s)
+}
+
+impl Force for Drag {
+    fn apply(&self, particle: &Particle, _particles: &[Particle]) -> Vec2 {
+        let velocity = particle.velocity;
+        -velocity * velocity.length() * self.quadratic / particle.mass - velocity * self.linear
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moving(velocity: Vec2, mass: f32) -> Particle {
+        Particle::new(Vec2::new(3.0, 4.0), velocity, 0.5, mass, WHITE)
+    }
+
+    #[test]
+    fn gravity_is_the_same_for_every_particle() {
+        let gravity = Gravity {
+            acceleration: Vec2::new(0.5, -9.8),
+        };
+        let light = moving(Vec2::ZERO, 0.1);
+        let heavy = moving(Vec2::new(20.0, 5.0), 100.0);
+        for p in [&light, &heavy] {
+            assert_eq!(gravity.apply(p, &[]), Vec2::new(0.5, -9.8));
+        }
+    }
+
+    #[test]
+    fn drag_opposes_velocity_and_spares_heavy_particles() {
+        let quadratic = Drag {
+            quadratic: 0.1,
+            linear: 0.0,
+        };
+        let velocity = Vec2::new(3.0, -4.0);
+        let light = quadratic.apply(&moving(velocity, 1.0), &[]);
+        assert!(light.abs_diff_eq(-velocity * 5.0 * 0.1, 1e-5), "{light}");
+        let heavy = quadratic.apply(&moving(velocity, 10.0), &[]);
+        assert!(heavy.abs_diff_eq(light / 10.0, 1e-5), "{heavy}");
+        assert_eq!(quadratic.apply(&moving(Vec2::ZERO, 1.0), &[]), Vec2::ZERO);
+
+        let linear = Drag {
+            quadratic: 0.0,
+            linear: 0.5,
+        };
+        for mass in [1.0, 10.0] {
+            let drag = linear.apply(&moving(velocity, mass), &[]);
+            assert!(drag.abs_diff_eq(-velocity * 0.5, 1e-6), "{drag}");
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use std::time::Instant;
+
+use crate::world::{CollisionEvent, World};
+
+// === Headless ===
+// Steps the world without opening a window, then prints how long that took,
+// how many collisions it resolved and where everything ended up. The same
+// seed and config give the same final state every run, so this doubles as a
+// benchmark and a regression check.
+pub fn run(mut world: World, steps: usize, dt: f32) {
+    let start = Instant::now();
+    let (mut particle_hits, mut boundary_hits) = (0, 0);
+    for _ in 0..steps {
+        world.step(dt);
+        for event in world.collision_events() {
+            match event {
+                CollisionEvent::Particles { .. } => particle_hits += 1,
+                CollisionEvent::Boundary { .. } => boundary_hits += 1,
+            }
+        }
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "stepped in {:.3} ms ({:.2} µs per step)",
+        elapsed.as_secs_f64() * 1e3,
+        elapsed.as_secs_f64() * 1e6 / steps.max(1) as f64
+    );
+    println!("collisions: {particle_hits} between particles, {boundary_hits} with the boundary");
+    println!("after {steps} steps of {dt:.5}s:");
+    for (i, p) in world.particles.iter().enumerate() {
+        println!(
+            "particle {i}: position ({:.4}, {:.4}) velocity ({:.4}, {:.4})",
+            p.position.x, p.position.y, p.velocity.x, p.velocity.y
+        );
+    }
+}
+
+// Steps the world and tracks how far its mechanical energy strays from the
+// start. Only meaningful for a lossless setup (see lossless_world in main).
+// Returns whether the worst relative drift stayed within `tolerance`.
+pub fn check_energy(mut world: World, steps: usize, dt: f32, tolerance: f32) -> bool {
+    let initial = world.mechanical_energy();
+    let mut worst = 0.0f32;
+    for _ in 0..steps {
+        world.step(dt);
+        let drift = (world.mechanical_energy() - initial).abs() / initial.abs().max(f32::EPSILON);
+        worst = worst.max(drift);
+    }
+
+    let passed = worst <= tolerance;
+    println!(
+        "{:?}: energy {initial:.3} J -> {:.3} J over {steps} steps, worst drift {:.3}% (tolerance {:.3}%): {}",
+        world.physics.integrator,
+        world.mechanical_energy(),
+        worst * 100.0,
+        tolerance * 100.0,
+        if passed { "ok" } else { "FAILED" }
+    );
+    passed
+}
@@ -0,0 +1,57 @@
+use macroquad::prelude::*;
+use std::collections::HashMap;
+
+use crate::coords::{pixels_per_meter, world_to_screen};
+use crate::render::GRID_SPACING;
+
+const HEATMAP_HALF_LIFE: f32 = 1.0; // Seconds for a cell's count to fade to half
+const HEATMAP_SATURATION: f32 = 8.0; // Count drawn at full heat
+const HEATMAP_MIN_COUNT: f32 = 0.01; // Cells fading below this are forgotten
+const HEATMAP_MAX_ALPHA: f32 = 0.6;
+
+// === Collision Heatmap ===
+// Counts collisions per grid cell over a sliding window: each hit adds one to
+// the cell it happened in, and every count fades with HEATMAP_HALF_LIFE, so
+// the map shows where collisions have been happening lately. The broad phase
+// resizes its cells to the particles every step, so the map keeps its own
+// fixed cells, one per square of the meter grid.
+#[derive(Default)]
+pub struct Heatmap {
+    counts: HashMap<(i32, i32), f32>,
+}
+
+impl Heatmap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, point: Vec2) {
+        *self.counts.entry(cell_of(point)).or_insert(0.0) += 1.0;
+    }
+
+    pub fn decay(&mut self, dt: f32) {
+        let factor = 0.5_f32.powf(dt / HEATMAP_HALF_LIFE);
+        self.counts.retain(|_, count| {
+            *count *= factor;
+            *count > HEATMAP_MIN_COUNT
+        });
+    }
+
+    // Cells from transparent (few collisions) to hot red (HEATMAP_SATURATION or more).
+    pub fn draw(&self) {
+        let size = GRID_SPACING * pixels_per_meter();
+        for (&(x, y), &count) in &self.counts {
+            let heat = (count / HEATMAP_SATURATION).min(1.0);
+            // Top-left corner on screen, as y points down there
+            let corner = Vec2::new(x as f32, (y + 1) as f32) * GRID_SPACING;
+            let corner = world_to_screen(corner);
+            let color = Color::new(1.0, 0.15, 0.0, heat * HEATMAP_MAX_ALPHA);
+            draw_rectangle(corner.x, corner.y, size, size, color);
+        }
+    }
+}
+
+fn cell_of(point: Vec2) -> (i32, i32) {
+    let cell = (point / GRID_SPACING).floor();
+    (cell.x as i32, cell.y as i32)
+}
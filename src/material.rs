@@ -0,0 +1,46 @@
+// === Materials ===
+// Named surface and bulk properties. Applying one to a particle sets its own
+// restitution, wall friction and contact friction, which collisions then
+// combine with the other side's, and derives its mass from the density.
+#[derive(Clone, Copy, Debug)]
+pub struct Material {
+    pub name: &'static str,
+    pub restitution: f32,
+    pub friction: f32, // Share of wall-parallel velocity kept per step, like Physics::friction
+    pub contact_friction: f32, // Coulomb μ for sliding, like Physics::contact_friction
+    pub density: f32,  // Areal density, kg/m²
+}
+
+pub const RUBBER: Material = Material {
+    name: "Rubber",
+    restitution: 0.9,
+    friction: 0.95,
+    contact_friction: 0.9,
+    density: 1.5,
+};
+
+pub const STEEL: Material = Material {
+    name: "Steel",
+    restitution: 0.6,
+    friction: 0.995,
+    contact_friction: 0.4,
+    density: 8.0,
+};
+
+pub const WOOD: Material = Material {
+    name: "Wood",
+    restitution: 0.4,
+    friction: 0.97,
+    contact_friction: 0.5,
+    density: 0.7,
+};
+
+pub const ICE: Material = Material {
+    name: "Ice",
+    restitution: 0.2,
+    friction: 0.999,
+    contact_friction: 0.03,
+    density: 0.9,
+};
+
+pub const MATERIALS: [Material; 4] = [RUBBER, STEEL, WOOD, ICE];
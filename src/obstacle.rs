@@ -0,0 +1,50 @@
+use macroquad::prelude::*;
+
+use crate::coords::{pixels_per_meter, world_to_screen};
+
+// === Static Obstacles ===
+// Immovable pegs: particles bounce off them as if they had infinite mass.
+pub struct StaticCircle {
+    pub position: Vec2,
+    pub radius: f32,
+}
+
+impl StaticCircle {
+    pub fn new(position: Vec2, radius: f32) -> Self {
+        Self { position, radius }
+    }
+
+    pub fn draw(&self) {
+        let screen_pos = world_to_screen(self.position);
+        let screen_radius = self.radius * pixels_per_meter();
+        draw_circle(screen_pos.x, screen_pos.y, screen_radius, GRAY);
+    }
+}
+
+// A fixed line segment from `a` to `b`, for ramps, funnels and internal walls.
+pub struct Wall {
+    pub a: Vec2,
+    pub b: Vec2,
+}
+
+impl Wall {
+    pub fn new(a: Vec2, b: Vec2) -> Self {
+        Self { a, b }
+    }
+
+    pub fn closest_point(&self, point: Vec2) -> Vec2 {
+        let ab = self.b - self.a;
+        let length_sq = ab.length_squared();
+        if length_sq == 0.0 {
+            return self.a;
+        }
+        let t = ((point - self.a).dot(ab) / length_sq).clamp(0.0, 1.0);
+        self.a + ab * t
+    }
+
+    pub fn draw(&self) {
+        let a = world_to_screen(self.a);
+        let b = world_to_screen(self.b);
+        draw_line(a.x, a.y, b.x, b.y, 3.0, GRAY);
+    }
+}
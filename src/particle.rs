@@ -0,0 +1,998 @@
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use crate::boundary::{Boundary, BoundaryShape};
+use crate::coords::{pixels_per_meter, world_to_screen};
+use crate::material::Material;
+use crate::obstacle::{StaticCircle, Wall};
+use crate::physics::{Integrator, Physics};
+use crate::render::{DrawOptions, speed_to_color};
+use crate::scene::color_format;
+use crate::shape::{Contact, Shape, time_of_impact};
+
+// Default for Physics::velocity_threshold: bounces off walls and pegs slower
+// than this are killed. It has to exceed the bounce one step of gravity
+// produces (restitution * |g| * dt ≈ 0.11 m/s at 60 Hz), or resting bodies
+// hop up and down forever.
+pub const VELOCITY_THRESHOLD: f32 = 0.2;
+pub const TRAIL_LENGTH: usize = 60; // Positions remembered per particle, one per step
+
+// === Particle ===
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Particle {
+    pub position: Vec2,
+    #[serde(default)]
+    pub velocity: Vec2,
+    pub shape: Shape,
+    pub mass: f32,
+    #[serde(with = "color_format")]
+    pub color: Color,
+    #[serde(default)]
+    pub orientation: f32, // Radians, counter-clockwise
+    #[serde(default)]
+    pub angular_velocity: f32, // Radians per second, counter-clockwise
+    #[serde(default)]
+    pub restitution: Option<f32>, // Falls back to Physics::restitution when None
+    #[serde(default)]
+    pub friction: Option<f32>, // Falls back to Physics::friction when None
+    #[serde(default)]
+    pub contact_friction: Option<f32>, // Falls back to Physics::contact_friction when None
+    #[serde(default)]
+    pub pinned: bool, // Held in place: never moves, and has infinite mass in collisions
+    #[serde(default)]
+    pub lifetime: Option<f32>, // Seconds until removal, None lives forever
+    #[serde(default)]
+    pub age: f32, // Seconds simulated so far
+    #[serde(skip)]
+    sleeping: bool, // At rest: skipped by integration and immovable until woken
+    #[serde(skip)]
+    still_steps: u32, // Consecutive steps spent below the velocity threshold
+    #[serde(skip)]
+    previous_position: Vec2, // Where the last update started, for swept collisions
+    #[serde(skip)]
+    previous_acceleration: Vec2, // What Verlet ended the last step with, to start the next
+    #[serde(skip)]
+    force: Vec2, // External forces accumulated for the next update
+    #[serde(skip)]
+    trail: VecDeque<Vec2>, // Recent positions, oldest first
+}
+
+impl Particle {
+    pub fn new(position: Vec2, velocity: Vec2, radius: f32, mass: f32, color: Color) -> Self {
+        Self::with_shape(position, velocity, Shape::Circle { radius }, mass, color)
+    }
+
+    // A circle whose mass follows its size: `density` is in kg/m² (this is a
+    // 2D world, so mass is density times area π·r², with r in meters).
+    pub fn from_density(
+        position: Vec2,
+        velocity: Vec2,
+        radius: f32,
+        density: f32,
+        color: Color,
+    ) -> Self {
+        let mass = density * std::f32::consts::PI * radius * radius;
+        Self::new(position, velocity, radius, mass, color)
+    }
+
+    pub fn new_box(
+        position: Vec2,
+        velocity: Vec2,
+        half_extents: Vec2,
+        mass: f32,
+        color: Color,
+    ) -> Self {
+        Self::with_shape(
+            position,
+            velocity,
+            Shape::Aabb { half_extents },
+            mass,
+            color,
+        )
+    }
+
+    fn with_shape(position: Vec2, velocity: Vec2, shape: Shape, mass: f32, color: Color) -> Self {
+        Self {
+            position,
+            velocity,
+            shape,
+            mass,
+            color,
+            orientation: 0.0,
+            angular_velocity: 0.0,
+            restitution: None,
+            friction: None,
+            contact_friction: None,
+            pinned: false,
+            lifetime: None,
+            age: 0.0,
+            sleeping: false,
+            still_steps: 0,
+            previous_position: position,
+            previous_acceleration: Vec2::ZERO,
+            force: Vec2::ZERO,
+            trail: VecDeque::with_capacity(TRAIL_LENGTH),
+        }
+    }
+
+    // Gives this particle its own bounciness and wall friction instead of the global ones.
+    pub fn with_surface(mut self, restitution: f32, friction: f32) -> Self {
+        self.restitution = Some(restitution);
+        self.friction = Some(friction);
+        self
+    }
+
+    // Takes on a material's surface, and a mass from its density and our size.
+    pub fn with_material(mut self, material: &Material) -> Self {
+        self.mass = material.density * self.shape.area();
+        self.contact_friction = Some(material.contact_friction);
+        self.with_surface(material.restitution, material.friction)
+    }
+
+    // Fixes the particle in place, e.g. as the anchor of a hanging chain.
+    pub fn pin(mut self) -> Self {
+        self.pinned = true;
+        self
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.lifetime.is_some_and(|lifetime| self.age >= lifetime)
+    }
+
+    // Draw opacity: fades from 1 to 0 over the last second of life.
+    fn fade(&self) -> f32 {
+        match self.lifetime {
+            Some(lifetime) => (lifetime - self.age).clamp(0.0, 1.0),
+            None => 1.0,
+        }
+    }
+
+    // Bounding radius: exact for circles, the corner distance for boxes.
+    pub fn radius(&self) -> f32 {
+        self.shape.bounding_radius()
+    }
+
+    pub fn is_sleeping(&self) -> bool {
+        self.sleeping
+    }
+
+    pub fn wake(&mut self) {
+        self.sleeping = false;
+        self.still_steps = 0;
+    }
+
+    // Pinned and sleeping particles are treated as immovable.
+    pub fn is_immovable(&self) -> bool {
+        self.pinned || self.sleeping
+    }
+
+    // Counts steps spent nearly still and falls asleep after
+    // `physics.sleep_steps` of them (0 never sleeps). Anything that has set
+    // it moving again, like an explosion, wakes it.
+    pub fn update_sleep(&mut self, physics: &Physics) {
+        let threshold = physics.velocity_threshold;
+        let still = self.velocity.length() < threshold
+            && (self.angular_velocity * self.spin_arm()).abs() < threshold;
+        if !still || physics.sleep_steps == 0 {
+            self.wake();
+            return;
+        }
+
+        self.still_steps = self.still_steps.saturating_add(1);
+        if self.still_steps >= physics.sleep_steps && !self.sleeping {
+            self.sleeping = true;
+            self.velocity = Vec2::ZERO;
+            self.angular_velocity = 0.0;
+        }
+    }
+
+    // 1 / mass, or 0 for a pinned or sleeping particle so impulses can't move it.
+    pub fn inverse_mass(&self) -> f32 {
+        if self.is_immovable() {
+            0.0
+        } else {
+            1.0 / self.mass
+        }
+    }
+
+    // Solid disc: 1 / (½·m·r²). Boxes stay axis-aligned, so they never spin,
+    // and immovable particles don't either.
+    pub fn inverse_inertia(&self) -> f32 {
+        if self.is_immovable() {
+            return 0.0;
+        }
+        match self.shape {
+            Shape::Circle { radius } => 1.0 / (0.5 * self.mass * radius * radius),
+            Shape::Aabb { .. } => 0.0,
+        }
+    }
+
+    // Distance from the center to a contact point that spin acts through.
+    fn spin_arm(&self) -> f32 {
+        match self.shape {
+            Shape::Circle { radius } => radius,
+            Shape::Aabb { .. } => 0.0,
+        }
+    }
+
+    pub fn restitution(&self, physics: &Physics) -> f32 {
+        self.restitution.unwrap_or(physics.restitution)
+    }
+
+    pub fn friction(&self, physics: &Physics) -> f32 {
+        self.friction.unwrap_or(physics.friction)
+    }
+
+    pub fn contact_friction(&self, physics: &Physics) -> f32 {
+        self.contact_friction.unwrap_or(physics.contact_friction)
+    }
+
+    pub fn momentum(&self) -> Vec2 {
+        self.velocity * self.mass
+    }
+
+    // Changes momentum by `impulse` (N·s = kg·m/s) at once, i.e. velocity by
+    // impulse / mass, waking a sleeper. Pinned particles stay put.
+    pub fn apply_impulse(&mut self, impulse: Vec2) {
+        if self.pinned {
+            return;
+        }
+        self.wake();
+        self.velocity += impulse / self.mass;
+    }
+
+    // Adds a force (N) that acts during the next update only.
+    pub fn apply_force(&mut self, force: Vec2) {
+        self.force += force;
+    }
+
+    // Acceleration from the forces gathered this step (gravity and drag among them).
+    fn acceleration(&self) -> Vec2 {
+        self.force / self.mass
+    }
+
+    // Puts the particle somewhere new as if it had always been there, so
+    // neither swept collisions nor drawing see it travel.
+    pub fn move_to(&mut self, position: Vec2) {
+        self.position = position;
+        self.previous_position = position;
+    }
+
+    // Where the particle was `alpha` of the way through the last step.
+    pub fn interpolated_position(&self, alpha: f32) -> Vec2 {
+        self.previous_position.lerp(self.position, alpha)
+    }
+
+    // First half of a step, run before the step's forces are gathered.
+    // Velocity Verlet moves here, with a half kick from the acceleration it
+    // ended the last step with, so the forces are then found at the new
+    // position; Euler and position Verlet only need the forces where the
+    // particle already is.
+    pub fn begin_update(&mut self, physics: &Physics, dt: f32) {
+        self.previous_position = self.position;
+        self.age += dt;
+        if self.is_immovable() {
+            return;
+        }
+        if physics.integrator == Integrator::Verlet {
+            let previous = self.previous_acceleration;
+            self.position += self.velocity * dt + 0.5 * previous * dt * dt;
+            self.velocity += 0.5 * previous * dt;
+        }
+    }
+
+    // Second half of a step, once every force on the particle is in.
+    pub fn finish_update(&mut self, physics: &Physics, dt: f32) {
+        if self.pinned {
+            // Whatever pushed on it this step is absorbed by whatever holds it
+            self.velocity = Vec2::ZERO;
+            self.angular_velocity = 0.0;
+            self.force = Vec2::ZERO;
+            return;
+        }
+        if self.sleeping {
+            self.force = Vec2::ZERO;
+            return;
+        }
+
+        let acceleration = self.acceleration();
+        match physics.integrator {
+            Integrator::Euler => {
+                // Semi-implicit Euler: kick the velocity, then move with the new velocity
+                self.velocity += acceleration * dt;
+                self.position += self.velocity * dt;
+            }
+            Integrator::Verlet => {
+                // Velocity Verlet: the other half kick, from the acceleration
+                // at the position begin_update moved to
+                self.velocity += 0.5 * acceleration * dt;
+                self.previous_acceleration = acceleration;
+            }
+            Integrator::PositionVerlet => {
+                // Position Verlet: x_next = 2x - x_prev + a·dt². The position
+                // a step back is recovered from the velocity rather than kept,
+                // so impulses from collisions and the walls carry over. The
+                // velocity kept is (x_next - x)/dt plus a half kick, the speed
+                // at x_next rather than halfway there.
+                let half_step = self.velocity - 0.5 * self.previous_acceleration * dt;
+                let previous = self.position - half_step * dt;
+                let next = 2.0 * self.position - previous + acceleration * dt * dt;
+                self.velocity = (next - self.position) / dt + 0.5 * acceleration * dt;
+                self.position = next;
+                self.previous_acceleration = acceleration;
+            }
+        }
+
+        self.orientation += self.angular_velocity * dt;
+        self.force = Vec2::ZERO;
+    }
+
+    // Friction model: on every step a particle touches the boundary, the
+    // velocity component parallel to that wall is multiplied by `friction`,
+    // whichever wall it is. On top of that, each contact applies Coulomb
+    // contact friction, which is what sets particles rolling.
+    // Returns the impulse (N·s) the boundary pushed back with in real hits,
+    // adding up both walls in a corner, or zero if the particle only rested
+    // against it or didn't touch it at all.
+    pub fn handle_boundary_collision(&mut self, physics: &Physics, bounds: &Boundary) -> Vec2 {
+        if let BoundaryShape::Circle { center, radius } = bounds.shape {
+            return self.handle_circular_boundary_collision(physics, center, radius);
+        }
+
+        let extents = self.shape.half_extents();
+        let restitution = physics.boundary_restitution();
+        let friction = self.friction(physics);
+        // Each wall's inward normal, and how far past it the particle is
+        let walls = [
+            (Vec2::Y, bounds.bottom + extents.y - self.position.y),
+            (Vec2::NEG_Y, self.position.y - (bounds.top - extents.y)),
+            (Vec2::X, bounds.left + extents.x - self.position.x),
+            (Vec2::NEG_X, self.position.x - (bounds.right - extents.x)),
+        ];
+        let mut impulse = Vec2::ZERO;
+        for (normal, depth) in walls {
+            if depth < 0.0 {
+                continue;
+            }
+            self.position += normal * depth;
+            let tangent = normal.perp();
+            let slide = self.velocity.dot(tangent);
+            self.velocity -= tangent * slide * (1.0 - friction);
+            if let Some(hit) = self.bounce_off_fixed(normal, depth, restitution, physics) {
+                impulse += normal * hit;
+            }
+        }
+        impulse
+    }
+
+    // For the solver's later passes, after contacts have shoved the particle
+    // past the boundary: moves it back inside and stops it moving further
+    // out, the way resting on the wall would. The bounce and the wall
+    // friction are handle_boundary_collision's, once a step.
+    pub fn hold_inside(&mut self, bounds: &Boundary) {
+        let before = self.position;
+        self.clamp_inside(bounds);
+        let push = self.position - before;
+        // A rectangle pushes back along each axis on its own, a circle toward its center
+        let pushes = match bounds.shape {
+            BoundaryShape::Rect => [Vec2::new(push.x, 0.0), Vec2::new(0.0, push.y)],
+            BoundaryShape::Circle { .. } => [push, Vec2::ZERO],
+        };
+        for push in pushes {
+            let Some(normal) = push.try_normalize() else {
+                continue;
+            };
+            self.velocity -= normal * self.velocity.dot(normal).min(0.0);
+        }
+    }
+
+    // Moves the particle back inside the boundary if it has been pushed past
+    // it, leaving its velocity alone.
+    fn clamp_inside(&mut self, bounds: &Boundary) {
+        match bounds.shape {
+            BoundaryShape::Rect => {
+                let extents = self.shape.half_extents();
+                self.position = self.position.clamp(
+                    Vec2::new(bounds.left, bounds.bottom) + extents,
+                    Vec2::new(bounds.right, bounds.top) - extents,
+                );
+            }
+            BoundaryShape::Circle { center, radius } => {
+                let offset = self.position - center;
+                let Some(outward) = offset.try_normalize() else {
+                    return;
+                };
+                let limit = radius - self.shape.support(outward);
+                if offset.length() > limit {
+                    self.position = center + outward * limit;
+                }
+            }
+        }
+    }
+
+    // Inside a round container the wall's inward normal points from the
+    // particle back toward the center.
+    fn handle_circular_boundary_collision(
+        &mut self,
+        physics: &Physics,
+        center: Vec2,
+        radius: f32,
+    ) -> Vec2 {
+        let offset = self.position - center;
+        let distance = offset.length();
+        if distance == 0.0 {
+            return Vec2::ZERO;
+        }
+
+        let outward = offset / distance;
+        let limit = radius - self.shape.support(outward);
+        if distance <= limit {
+            return Vec2::ZERO;
+        }
+        self.position = center + outward * limit;
+        let tangent = outward.perp();
+        let slide = self.velocity.dot(tangent);
+        self.velocity -= tangent * slide * (1.0 - self.friction(physics));
+        let restitution = physics.boundary_restitution();
+        self.bounce_off_fixed(-outward, distance - limit, restitution, physics)
+            .map_or(Vec2::ZERO, |hit| -outward * hit)
+    }
+
+    pub fn handle_obstacle_collision(&mut self, physics: &Physics, obstacle: &StaticCircle) {
+        let delta = self.position - obstacle.position;
+        let distance = delta.length();
+        if distance == 0.0 {
+            return;
+        }
+
+        let normal = delta / distance;
+        let min_dist = obstacle.radius + self.shape.support(normal);
+        if distance >= min_dist {
+            return;
+        }
+
+        // The obstacle never moves, so the particle takes the whole correction
+        self.position = obstacle.position + normal * min_dist;
+        let restitution = self.restitution(physics);
+        self.bounce_off_fixed(normal, min_dist - distance, restitution, physics);
+    }
+
+    pub fn handle_wall_collision(&mut self, physics: &Physics, wall: &Wall) {
+        let closest = wall.closest_point(self.position);
+        let delta = self.position - closest;
+        let distance = delta.length();
+
+        // A center exactly on the segment has no direction to go, so use the
+        // segment's own normal
+        let normal = if distance > 0.0 {
+            delta / distance
+        } else {
+            (wall.b - wall.a).perp().normalize_or(Vec2::Y)
+        };
+        let reach = self.shape.support(normal);
+        if distance >= reach {
+            return;
+        }
+        self.position = closest + normal * reach;
+        let restitution = self.restitution(physics);
+        self.bounce_off_fixed(normal, reach - distance, restitution, physics);
+    }
+
+    // Swept check against a peg: if this step's path ran into it, back up to
+    // the moment of impact and bounce there instead of ending up inside or
+    // past it. Boxes are left to the discrete check.
+    pub fn sweep_obstacle_collision(&mut self, physics: &Physics, obstacle: &StaticCircle) {
+        let Shape::Circle { radius } = self.shape else {
+            return;
+        };
+        let Some(t) = time_of_impact(
+            self.previous_position - obstacle.position,
+            self.position - obstacle.position,
+            obstacle.radius + radius,
+        ) else {
+            return;
+        };
+
+        self.rewind_to(t);
+        let normal = (self.position - obstacle.position).normalize_or(Vec2::Y);
+        self.bounce_off_fixed(normal, 0.0, self.restitution(physics), physics);
+    }
+
+    // Swept check against a wall: catches a path that crosses the segment's
+    // line within one step, which the discrete check would miss or resolve
+    // onto the wrong side.
+    pub fn sweep_wall_collision(&mut self, physics: &Physics, wall: &Wall) {
+        let Shape::Circle { radius } = self.shape else {
+            return;
+        };
+        let Some(line_normal) = (wall.b - wall.a).perp().try_normalize() else {
+            return;
+        };
+
+        // Distances from the wall's line, measured on the side the step started
+        let start = (self.previous_position - wall.a).dot(line_normal);
+        let side = start.signum();
+        let start = start * side;
+        let end = (self.position - wall.a).dot(line_normal) * side;
+        if start < radius || end >= radius {
+            return;
+        }
+
+        let t = (start - radius) / (start - end);
+        let hit = self.previous_position.lerp(self.position, t);
+        if wall.closest_point(hit).distance(hit) > radius * 1.001 {
+            return; // Passed beside the segment, not through it
+        }
+
+        self.rewind_to(t);
+        let restitution = self.restitution(physics);
+        self.bounce_off_fixed(line_normal * side, 0.0, restitution, physics);
+    }
+
+    // Moves back along this step's path to the given fraction of it.
+    fn rewind_to(&mut self, t: f32) {
+        self.position = self.previous_position.lerp(self.position, t);
+    }
+
+    // Reflects the normal velocity off an immovable surface the particle was
+    // just pushed `lift` meters back out of, with friction. Pushing it out
+    // lifts it without slowing it, which on its own would add energy on every
+    // bounce, so it arrives with only the speed left after climbing that far
+    // against gravity. An arrival slower than velocity_threshold comes to rest
+    // instead of bouncing, whichever way gravity points. Returns the normal
+    // impulse (N·s) if that was a real hit; None if it only rested there or
+    // was already moving away.
+    fn bounce_off_fixed(
+        &mut self,
+        normal: Vec2,
+        lift: f32,
+        restitution: f32,
+        physics: &Physics,
+    ) -> Option<f32> {
+        let into = -self.velocity.dot(normal);
+        if into <= 0.0 {
+            return None;
+        }
+        let climb = -physics.gravity.dot(normal) * lift;
+        let arriving = (into * into - 2.0 * climb).max(0.0).sqrt();
+        let hit = arriving > physics.velocity_threshold;
+        let speed_out = if hit { restitution * arriving } else { 0.0 };
+
+        // Resting contact still pushes back, and so still has friction
+        let normal_speed_change = speed_out + into;
+        self.velocity += normal_speed_change * normal;
+        let normal_impulse = self.mass * normal_speed_change;
+        self.apply_contact_friction(normal, normal_impulse, physics);
+        hit.then_some(normal_impulse)
+    }
+
+    // How far the particle moved during the last update.
+    pub fn displacement(&self) -> f32 {
+        self.position.distance(self.previous_position)
+    }
+
+    // Coulomb friction against a fixed surface with the given outward normal.
+    // Opposes sliding of the contact point, turning some of it into spin so
+    // particles roll, and is capped at μ times the normal impulse the contact
+    // just delivered. Fixed surfaces have the configured contact_friction,
+    // combined with the particle's own like two particles' are.
+    fn apply_contact_friction(&mut self, normal: Vec2, normal_impulse: f32, physics: &Physics) {
+        let tangent = normal.perp();
+        let arm = self.spin_arm();
+        let slip = self.velocity.dot(tangent) - self.angular_velocity * arm;
+        let effective_mass = 1.0 / self.mass + arm * arm * self.inverse_inertia();
+        let mu = (self.contact_friction(physics) * physics.contact_friction).sqrt();
+        let max_friction = mu * normal_impulse;
+        let friction_impulse = (-slip / effective_mass).clamp(-max_friction, max_friction);
+
+        self.velocity += friction_impulse * tangent / self.mass;
+        self.angular_velocity -= friction_impulse * arm * self.inverse_inertia();
+    }
+
+    // Remembers the current position, forgetting the oldest once the trail is full.
+    pub fn record_trail(&mut self) {
+        if self.trail.len() == TRAIL_LENGTH {
+            self.trail.pop_front();
+        }
+        self.trail.push_back(self.position);
+    }
+
+    // Forgets the trail but keeps its buffer for reuse.
+    pub fn clear_trail(&mut self) {
+        self.trail.clear();
+    }
+
+    // Line segments through the recent positions, fading out with age.
+    pub fn draw_trail(&self) {
+        let ppm = pixels_per_meter();
+        let thickness = (self.radius() * ppm * 0.25).max(1.0);
+        let len = self.trail.len() as f32;
+
+        for (i, (from, to)) in self.trail.iter().zip(self.trail.iter().skip(1)).enumerate() {
+            let from = world_to_screen(*from);
+            let to = world_to_screen(*to);
+            let alpha = self.color.a * self.fade() * (i + 1) as f32 / len;
+            let color = Color::new(self.color.r, self.color.g, self.color.b, alpha);
+            draw_line(from.x, from.y, to.x, to.y, thickness, color);
+        }
+    }
+
+    pub fn draw(&self, options: &DrawOptions) {
+        let position = self.interpolated_position(options.interpolation);
+        let screen_pos = world_to_screen(position);
+        let ppm = pixels_per_meter();
+        let mut color = if options.color_by_speed {
+            speed_to_color(self.velocity.length(), options.max_speed)
+        } else {
+            self.color
+        };
+        if options.tint_sleeping && self.sleeping {
+            // Dim and blue-shifted, so the settled part of a pile stands out
+            color = Color::new(color.r * 0.3, color.g * 0.3, color.b * 0.3 + 0.35, color.a);
+        }
+        color.a *= self.fade();
+
+        match self.shape {
+            Shape::Circle { radius } => {
+                draw_circle(screen_pos.x, screen_pos.y, radius * ppm, color);
+
+                // Radial marker so spin is visible
+                let rim = world_to_screen(position + Vec2::from_angle(self.orientation) * radius);
+                draw_line(screen_pos.x, screen_pos.y, rim.x, rim.y, 1.5, BLACK);
+            }
+            Shape::Aabb { half_extents } => {
+                let top_left =
+                    world_to_screen(position + Vec2::new(-half_extents.x, half_extents.y));
+                let size = half_extents * 2.0 * ppm;
+                draw_rectangle(top_left.x, top_left.y, size.x, size.y, color);
+            }
+        }
+    }
+
+    // Traces the particle's edge, for highlighting it.
+    pub fn draw_outline(&self, options: &DrawOptions, thickness: f32, color: Color) {
+        let position = self.interpolated_position(options.interpolation);
+        let screen_pos = world_to_screen(position);
+        let ppm = pixels_per_meter();
+        match self.shape {
+            Shape::Circle { radius } => {
+                draw_circle_lines(screen_pos.x, screen_pos.y, radius * ppm, thickness, color);
+            }
+            Shape::Aabb { half_extents } => {
+                let top_left =
+                    world_to_screen(position + Vec2::new(-half_extents.x, half_extents.y));
+                let size = half_extents * 2.0 * ppm;
+                draw_rectangle_lines(top_left.x, top_left.y, size.x, size.y, thickness, color);
+            }
+        }
+    }
+}
+
+// Swept check between two circles: if their paths met during the step, both
+// are rewound to the moment of contact and the collision is resolved there.
+pub fn sweep_particle_collision(p1: &mut Particle, p2: &mut Particle, physics: &Physics) {
+    let (Shape::Circle { radius: r1 }, Shape::Circle { radius: r2 }) = (p1.shape, p2.shape) else {
+        return;
+    };
+    let Some(t) = time_of_impact(
+        p2.previous_position - p1.previous_position,
+        p2.position - p1.position,
+        r1 + r2,
+    ) else {
+        return;
+    };
+
+    p1.rewind_to(t);
+    p2.rewind_to(t);
+    let normal = (p2.position - p1.position).normalize_or(Vec2::Y);
+    resolve_contact(p1, p2, Contact { normal, depth: 0.0 }, physics, 0.0);
+}
+
+// Pushes apart and bounces two particles found touching by shape::contact.
+// `dt` is only needed for the Baumgarte bias, which is zero without overlap.
+// Returns the normal impulse if the pair met faster than velocity_threshold,
+// a real hit. The world solves pairs before anything stops either particle
+// this step, so a resting pair sharing this step's kick from gravity doesn't
+// look like it's meeting; only a difference in how they move does.
+pub fn resolve_contact(
+    p1: &mut Particle,
+    p2: &mut Particle,
+    contact: Contact,
+    physics: &Physics,
+    dt: f32,
+) -> Option<f32> {
+    let Contact {
+        normal,
+        depth: overlap,
+    } = contact;
+
+    // A real hit wakes a sleeper; gentle resting contact leaves it asleep
+    let approach = -(p2.velocity - p1.velocity).dot(normal);
+    let hit = approach > physics.velocity_threshold;
+    if hit {
+        p1.wake();
+        p2.wake();
+    }
+
+    let (inv_mass1, inv_mass2) = (p1.inverse_mass(), p2.inverse_mass());
+    let total_inv_mass = inv_mass1 + inv_mass2;
+    if total_inv_mass == 0.0 {
+        return hit.then_some(0.0); // Neither can give way
+    }
+
+    // Only impulses equal and opposite on both particles follow, so unless one
+    // is immovable (and absorbs momentum) the pair's total must come out the same
+    let momentum_before = p1.momentum() + p2.momentum();
+
+    // Separate particles, the lighter one moving further. Pushing out the
+    // whole overlap at once makes neighbouring contacts in a pile overshoot
+    // and fight each other, so close only part of it each pass. Only what's
+    // past half the slop is worked off: a resting contact keeps a little
+    // overlap rather than being nudged every step, and a pile settles about
+    // midway into the slop instead of sinking to its edge and being shoved
+    // back out over and over.
+    let penetration = (overlap - 0.5 * physics.correction_slop).max(0.0);
+    let correction = penetration * physics.correction_percent;
+    p1.position -= normal * correction * (inv_mass1 / total_inv_mass);
+    p2.position += normal * correction * (inv_mass2 / total_inv_mass);
+
+    // Calculate impulse
+    let rel_vel = p2.velocity - p1.velocity;
+    let vel_along_normal = rel_vel.dot(normal);
+
+    // Baumgarte bias: ask for a little extra separating speed in proportion
+    // to whatever overlap the correction above left, so a loaded stack climbs
+    // back out of itself instead of slowly sinking. What was already pushed
+    // apart isn't asked for again as speed, which would pop the pair apart.
+    // Nothing changes without overlap.
+    let bias = if dt > 0.0 {
+        physics.baumgarte * (penetration - correction) / dt
+    } else {
+        0.0
+    };
+
+    // Combine both surfaces so the bouncier one doesn't dominate
+    let restitution = (p1.restitution(physics) * p2.restitution(physics)).sqrt();
+    let target = bias + (-restitution * vel_along_normal).max(0.0);
+    if vel_along_normal >= target {
+        return hit.then_some(0.0); // Already separating fast enough
+    }
+    let impulse = (target - vel_along_normal) / total_inv_mass;
+    let impulse_vec = impulse * normal;
+
+    p1.velocity -= impulse_vec * inv_mass1;
+    p2.velocity += impulse_vec * inv_mass2;
+    debug_assert!(
+        (p2.velocity - p1.velocity).dot(normal) >= -1e-4 * vel_along_normal.abs(),
+        "particles still approaching after the normal impulse"
+    );
+
+    // Friction impulse opposing the sliding of the two surfaces at the contact
+    // point (spin included), capped by the Coulomb limit of μ times the normal
+    // impulse, μ combining both surfaces like restitution. Acting off-center,
+    // it also spins both particles.
+    let tangent = normal.perp();
+    let slip = p2.velocity.dot(tangent)
+        - p2.angular_velocity * p2.spin_arm()
+        - p1.velocity.dot(tangent)
+        - p1.angular_velocity * p1.spin_arm();
+    let effective_mass = total_inv_mass
+        + p1.spin_arm() * p1.spin_arm() * p1.inverse_inertia()
+        + p2.spin_arm() * p2.spin_arm() * p2.inverse_inertia();
+    let mu = (p1.contact_friction(physics) * p2.contact_friction(physics)).sqrt();
+    let max_friction = mu * impulse;
+    let friction_impulse = (-slip / effective_mass).clamp(-max_friction, max_friction);
+
+    p1.velocity -= friction_impulse * tangent * inv_mass1;
+    p2.velocity += friction_impulse * tangent * inv_mass2;
+    p1.angular_velocity -= friction_impulse * p1.spin_arm() * p1.inverse_inertia();
+    p2.angular_velocity -= friction_impulse * p2.spin_arm() * p2.inverse_inertia();
+
+    debug_assert!(
+        p1.is_immovable()
+            || p2.is_immovable()
+            || (p1.momentum() + p2.momentum()).abs_diff_eq(
+                momentum_before,
+                1e-4 * (momentum_before.length() + impulse.abs() + 1.0)
+            ),
+        "collision changed the pair's total momentum"
+    );
+    hit.then_some(impulse)
+}
+
+// Index of the topmost (last drawn) particle containing the point.
+pub fn particle_at(particles: &[Particle], point: Vec2) -> Option<usize> {
+    particles
+        .iter()
+        .rposition(|p| p.shape.contains(point - p.position))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shape::contact;
+
+    const DT: f32 = 1.0 / 60.0;
+
+    // Two unit-diameter circles along the x axis, `gap` apart edge to edge
+    // (negative for an overlap).
+    fn pair(gap: f32, (m1, v1): (f32, f32), (m2, v2): (f32, f32)) -> (Particle, Particle) {
+        let p1 = Particle::new(Vec2::ZERO, Vec2::new(v1, 0.0), 0.5, m1, WHITE);
+        let p2 = Particle::new(
+            Vec2::new(1.0 + gap, 0.0),
+            Vec2::new(v2, 0.0),
+            0.5,
+            m2,
+            WHITE,
+        );
+        (p1, p2)
+    }
+
+    fn collide(p1: &mut Particle, p2: &mut Particle) -> Option<f32> {
+        let contact =
+            contact(&p1.shape, p1.position, &p2.shape, p2.position).expect("not touching");
+        resolve_contact(p1, p2, contact, &Physics::default(), DT)
+    }
+
+    fn assert_head_on(m1: f32, m2: f32) {
+        let (mut p1, mut p2) = pair(-0.05, (m1, 3.0), (m2, -2.0));
+        let momentum = p1.momentum() + p2.momentum();
+        let impulse = collide(&mut p1, &mut p2).expect("a head-on collision is a hit");
+
+        assert!(impulse > 0.0);
+        let after = p1.momentum() + p2.momentum();
+        assert!(
+            after.abs_diff_eq(momentum, 1e-5),
+            "momentum {momentum} became {after}"
+        );
+        let separating = p2.velocity.x - p1.velocity.x;
+        let restitution = Physics::default().restitution;
+        assert!(separating > 0.0, "still approaching at {separating} m/s");
+        assert!(
+            separating >= restitution * 5.0 - 1e-5,
+            "separating at only {separating} m/s"
+        );
+        assert!(p2.position.x - p1.position.x > 0.95, "overlap not reduced");
+    }
+
+    #[test]
+    fn head_on_equal_masses() {
+        assert_head_on(1.0, 1.0);
+    }
+
+    #[test]
+    fn head_on_unequal_masses() {
+        assert_head_on(1.0, 4.0);
+        assert_head_on(10.0, 0.5);
+    }
+
+    #[test]
+    fn heavier_particle_is_deflected_less() {
+        let (mut light, mut heavy) = pair(-0.02, (1.0, 2.0), (5.0, -2.0));
+        collide(&mut light, &mut heavy);
+        assert!((heavy.velocity.x + 2.0).abs() < (light.velocity.x - 2.0).abs());
+    }
+
+    #[test]
+    fn already_separating_pair_is_left_alone() {
+        // Overlapping by less than half the slop, so nothing pushes them apart either
+        let (mut p1, mut p2) = pair(-0.004, (1.0, -1.0), (2.0, 1.0));
+        let (before1, before2) = (p1.clone(), p2.clone());
+        assert_eq!(collide(&mut p1, &mut p2), None);
+        assert_eq!(p1.velocity, before1.velocity);
+        assert_eq!(p2.velocity, before2.velocity);
+        assert_eq!(p1.position, before1.position);
+        assert_eq!(p2.position, before2.position);
+    }
+
+    // A particle moving along +x clipping a resting one above its path, off
+    // center. Gives back the struck particle, the contact normal and the
+    // normal impulse.
+    fn glancing_blow(contact_friction: f32) -> (Particle, Vec2, f32) {
+        let mut p1 = Particle::new(Vec2::ZERO, Vec2::new(4.0, 0.0), 0.5, 1.0, WHITE);
+        let mut p2 = Particle::new(Vec2::new(0.8, 0.58), Vec2::ZERO, 0.5, 1.0, WHITE);
+        let physics = Physics {
+            contact_friction,
+            ..Physics::default()
+        };
+        let contact = contact(&p1.shape, p1.position, &p2.shape, p2.position).unwrap();
+        let normal = contact.normal;
+        let impulse = resolve_contact(&mut p1, &mut p2, contact, &physics, DT).expect("a hit");
+        (p2, normal, impulse)
+    }
+
+    #[test]
+    fn off_center_hit_drags_the_stationary_particle_sideways() {
+        let (without, normal, _) = glancing_blow(0.0);
+        let (with, _, _) = glancing_blow(0.3);
+        let tangent = normal.perp();
+        // The mover slides past along -tangent, so friction pulls that way
+        assert!(Vec2::new(4.0, 0.0).dot(tangent) < 0.0);
+
+        assert!(without.velocity.dot(tangent).abs() < 1e-5);
+        assert!(
+            with.velocity.dot(tangent) < -0.05,
+            "picked up only {} m/s along the contact",
+            with.velocity.dot(tangent)
+        );
+        assert!(
+            with.angular_velocity != 0.0,
+            "friction off center didn't spin it"
+        );
+    }
+
+    #[test]
+    fn contact_friction_stays_within_the_coulomb_limit() {
+        for contact_friction in [0.02, 0.1, 0.3, 1.0] {
+            let (struck, normal, impulse) = glancing_blow(contact_friction);
+            // Unit mass, so its momentum along the contact is the friction impulse
+            let friction_impulse = struck.velocity.dot(normal.perp()).abs();
+            assert!(
+                friction_impulse <= contact_friction * impulse + 1e-5,
+                "μ = {contact_friction}: friction {friction_impulse} N·s past {} N·s",
+                contact_friction * impulse
+            );
+        }
+        // Light friction can't stop the slide, so it gives all the limit allows
+        let (struck, normal, impulse) = glancing_blow(0.02);
+        let friction_impulse = struck.velocity.dot(normal.perp()).abs();
+        assert!((friction_impulse - 0.02 * impulse).abs() < 1e-5);
+    }
+
+    // Each wall of a 10 m box by a point on it and its inward normal.
+    const WALLS: [(&str, Vec2, Vec2); 4] = [
+        ("floor", Vec2::new(5.0, 1.0), Vec2::Y),
+        ("ceiling", Vec2::new(5.0, 9.0), Vec2::NEG_Y),
+        ("left wall", Vec2::new(1.0, 5.0), Vec2::X),
+        ("right wall", Vec2::new(9.0, 5.0), Vec2::NEG_X),
+    ];
+
+    // A ball just into the given wall, driving into it at 3 m/s while
+    // sliding along it at 2 m/s, after the boundary has dealt with it.
+    fn skim_wall(wall: Vec2, normal: Vec2, physics: &Physics) -> Particle {
+        let position = wall + normal * 0.49;
+        let velocity = -normal * 3.0 + normal.perp() * 2.0;
+        let mut p = Particle::new(position, velocity, 0.5, 1.0, WHITE);
+        p.handle_boundary_collision(physics, &Boundary::from_dimensions(10.0, 10.0));
+        p
+    }
+
+    #[test]
+    fn every_wall_keeps_the_same_share_of_parallel_velocity() {
+        // Without contact friction, only the wall friction acts along the wall
+        let physics = Physics {
+            friction: 0.5,
+            contact_friction: 0.0,
+            ..Physics::default()
+        };
+        for (name, wall, normal) in WALLS {
+            let p = skim_wall(wall, normal, &physics);
+            let parallel = p.velocity.dot(normal.perp());
+            assert!(
+                (parallel - 1.0).abs() < 1e-5,
+                "{name}: sliding at {parallel} m/s, not 1"
+            );
+            assert!(p.velocity.dot(normal) > 0.0, "{name}: didn't bounce");
+        }
+    }
+
+    #[test]
+    fn container_bounciness_overrides_the_particles_own() {
+        let physics = Physics {
+            gravity: Vec2::ZERO,
+            boundary_restitution: Some(0.5),
+            ..Physics::default()
+        };
+        for (name, wall, normal) in WALLS {
+            let mut p = Particle::new(wall + normal * 0.49, -normal * 3.0, 0.5, 1.0, WHITE)
+                .with_surface(0.9, 1.0);
+            p.handle_boundary_collision(&physics, &Boundary::from_dimensions(10.0, 10.0));
+            let rebound = p.velocity.dot(normal);
+            assert!(
+                (rebound - 1.5).abs() < 1e-5,
+                "{name}: came off at {rebound} m/s, not 1.5"
+            );
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::particle::VELOCITY_THRESHOLD;
+
+pub const SLEEP_STEPS: u32 = 60; // One second at the default step
+pub const CORRECTION_SLOP: f32 = 0.01; // Overlap (m) resting particles may keep
+pub const CORRECTION_PERCENT: f32 = 0.8; // Share of an overlap past half the slop corrected per pass
+pub const SOLVER_ITERATIONS: u32 = 8; // Passes over the contacts per step
+pub const BAUMGARTE: f32 = 0.2; // Share of the overlap left after correction turned into separating speed
+
+// === Integrators ===
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Integrator {
+    Euler,          // Semi-implicit Euler
+    Verlet,         // Velocity Verlet, holds energy far better over long runs
+    PositionVerlet, // Position (Störmer) Verlet, stepping from the last two positions
+}
+
+impl Integrator {
+    pub fn next(self) -> Self {
+        match self {
+            Integrator::Euler => Integrator::Verlet,
+            Integrator::Verlet => Integrator::PositionVerlet,
+            Integrator::PositionVerlet => Integrator::Euler,
+        }
+    }
+
+    // Parses "euler", "verlet" or "position-verlet", ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "euler" => Some(Integrator::Euler),
+            "verlet" => Some(Integrator::Verlet),
+            "position-verlet" => Some(Integrator::PositionVerlet),
+            _ => None,
+        }
+    }
+}
+
+// === Physics ===
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Physics {
+    #[serde(deserialize_with = "gravity_format::deserialize")]
+    pub gravity: Vec2, // Uniform acceleration (m/s²); (0, -9.8) is ordinary downward gravity
+    pub gravity_spin: f32, // Rate (rad/s) the gravity direction turns, for a tumbling box
+    pub restitution: f32,  // Bounciness between particles, 0..=1
+    pub boundary_restitution: Option<f32>, // Bounciness against the container; unset follows restitution
+    pub friction: f32, // Share of wall-parallel velocity kept per step touching the boundary
+    pub contact_friction: f32, // Coulomb μ for sliding between particles
+    pub drag_coefficient: f32, // k in F_drag = -k * |v| * v
+    pub drag: f32,     // Linear drag: velocity loses drag * velocity per second
+    pub integrator: Integrator,
+    pub gravitational_constant: f32, // G for particle-particle attraction, 0 disables it
+    pub gravity_softening: f32,      // ε in r² + ε², keeps close passes finite
+    pub wind: Vec2,                  // Uniform acceleration (m/s²), the same for every mass
+    pub velocity_threshold: f32, // Bounces off walls and pegs slower than this (m/s) come to rest
+    pub sleep_steps: u32,        // Steps below velocity_threshold before a particle sleeps, 0 never
+    pub correction_slop: f32,    // Overlap (m) resting particles may keep; half of it is left alone
+    pub correction_percent: f32, // Share of an overlap past half the slop removed per pass, 0..=1
+    pub baumgarte: f32, // Share of the overlap left after correction fed back as separating velocity, 0..=1
+    pub solver_iterations: u32, // Passes over all particle contacts per step, at least 1
+    pub ccd: bool,      // Swept collision checks so fast particles can't tunnel, at extra cost
+}
+
+impl Default for Physics {
+    fn default() -> Self {
+        Self {
+            gravity: Vec2::new(0.0, -9.8),
+            gravity_spin: 0.0,
+            restitution: 0.7,
+            boundary_restitution: None,
+            friction: 0.99,
+            contact_friction: 0.3,
+            drag_coefficient: 0.1, // Adjust for stronger/weaker air resistance
+            drag: 0.0,
+            integrator: Integrator::Euler,
+            gravitational_constant: 0.0,
+            gravity_softening: 0.1,
+            wind: Vec2::ZERO,
+            velocity_threshold: VELOCITY_THRESHOLD,
+            sleep_steps: SLEEP_STEPS,
+            correction_slop: CORRECTION_SLOP,
+            correction_percent: CORRECTION_PERCENT,
+            baumgarte: BAUMGARTE,
+            solver_iterations: SOLVER_ITERATIONS,
+            ccd: false,
+        }
+    }
+}
+
+impl Physics {
+    pub fn boundary_restitution(&self) -> f32 {
+        self.boundary_restitution.unwrap_or(self.restitution)
+    }
+}
+
+// Gravity used to be a single vertical value; files written then still load,
+// with a plain number taken as (0, g).
+mod gravity_format {
+    use super::*;
+    use serde::Deserializer;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Gravity {
+        Vertical(f32),
+        Vector(Vec2),
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec2, D::Error> {
+        Ok(match Gravity::deserialize(deserializer)? {
+            Gravity::Vertical(g) => Vec2::new(0.0, g),
+            Gravity::Vector(g) => g,
+        })
+    }
+}
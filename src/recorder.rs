@@ -0,0 +1,34 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use crate::particle::Particle;
+
+// === Trajectory Recording ===
+// Writes every particle's position and velocity after each step as CSV rows
+// of step, id, x, y, vx, vy. The id is the particle's index at that step, so
+// it shifts down past any particle removed earlier. Rows are flushed as each
+// step finishes; a run cut short leaves a complete file up to that step.
+pub struct Recorder {
+    writer: BufWriter<File>,
+    step: u64,
+}
+
+impl Recorder {
+    pub fn create(path: &str) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "step,id,x,y,vx,vy")?;
+        Ok(Self { writer, step: 0 })
+    }
+
+    pub fn record(&mut self, particles: &[Particle]) -> io::Result<()> {
+        self.step += 1;
+        for (id, p) in particles.iter().enumerate() {
+            writeln!(
+                self.writer,
+                "{},{id},{},{},{},{}",
+                self.step, p.position.x, p.position.y, p.velocity.x, p.velocity.y
+            )?;
+        }
+        self.writer.flush()
+    }
+}
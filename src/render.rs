@@ -0,0 +1,255 @@
+use macroquad::prelude::*;
+
+use crate::coords::{pixels_per_meter, screen_to_world, world_to_screen};
+
+pub const SPEED_COLOR_MAX: f32 = 20.0; // Speed (m/s) that maps to the hot end of the gradient
+
+// === Render Options ===
+#[derive(Clone, Copy)]
+pub struct DrawOptions {
+    pub color_by_speed: bool,
+    pub max_speed: f32,      // Speed drawn fully red when coloring by speed
+    pub grid: bool,          // Meter gridlines behind everything
+    pub interpolation: f32,  // How far into the next step to draw particles, 0..=1
+    pub tint_sleeping: bool, // Draw sleeping particles dim blue
+    pub momentum: bool,      // Center of mass with an arrow for total momentum
+}
+
+impl Default for DrawOptions {
+    fn default() -> Self {
+        Self {
+            color_by_speed: false,
+            max_speed: SPEED_COLOR_MAX,
+            grid: false,
+            interpolation: 1.0,
+            tint_sleeping: false,
+            momentum: false,
+        }
+    }
+}
+
+// Maps a speed onto a blue (at rest) -> green -> red (at or above max_speed) gradient.
+pub fn speed_to_color(speed: f32, max_speed: f32) -> Color {
+    let t = (speed / max_speed).clamp(0.0, 1.0);
+    if t < 0.5 {
+        let s = t * 2.0;
+        Color::new(0.0, s, 1.0 - s, 1.0)
+    } else {
+        let s = (t - 0.5) * 2.0;
+        Color::new(s, 1.0 - s, 0.0, 1.0)
+    }
+}
+
+// === Grid ===
+pub const GRID_SPACING: f32 = 1.0; // Meters between gridlines
+const GRID_LABEL_GAP: f32 = 40.0; // Labels at least this many pixels apart
+const GRID_COLOR: Color = Color::new(1.0, 1.0, 1.0, 0.08);
+const GRID_LABEL_COLOR: Color = Color::new(1.0, 1.0, 1.0, 0.4);
+
+// Faint lines at every whole GRID_SPACING of world space across the visible
+// view, labelled in meters along the bottom and left edges of the screen.
+// Zoomed out, only every few lines get a label so they don't run together.
+pub fn draw_grid() {
+    let (width, height) = (screen_width(), screen_height());
+    let top_left = screen_to_world(Vec2::ZERO);
+    let bottom_right = screen_to_world(Vec2::new(width, height));
+    let label_every = (GRID_LABEL_GAP / (GRID_SPACING * pixels_per_meter())).ceil() as i32;
+    let lines = |from: f32, to: f32| {
+        (from / GRID_SPACING).ceil() as i32..=(to / GRID_SPACING).floor() as i32
+    };
+
+    for i in lines(top_left.x, bottom_right.x) {
+        let x = world_to_screen(Vec2::new(i as f32 * GRID_SPACING, 0.0)).x;
+        draw_line(x, 0.0, x, height, 1.0, GRID_COLOR);
+        if i.rem_euclid(label_every.max(1)) == 0 {
+            let label = format!("{}", i as f32 * GRID_SPACING);
+            draw_text(&label, x + 2.0, height - 4.0, 16.0, GRID_LABEL_COLOR);
+        }
+    }
+    for i in lines(bottom_right.y, top_left.y) {
+        let y = world_to_screen(Vec2::new(0.0, i as f32 * GRID_SPACING)).y;
+        draw_line(0.0, y, width, y, 1.0, GRID_COLOR);
+        if i.rem_euclid(label_every.max(1)) == 0 {
+            let label = format!("{}", i as f32 * GRID_SPACING);
+            draw_text(&label, 2.0, y - 2.0, 16.0, GRID_LABEL_COLOR);
+        }
+    }
+}
+
+// === Momentum ===
+const MOMENTUM_ARROW_TIME: f32 = 1.0; // Seconds of center-of-mass travel the arrow spans
+const MOMENTUM_COLOR: Color = MAGENTA;
+
+// A cross at the center of mass and an arrow along the total momentum,
+// given divided by the total mass, i.e. as the center of mass's velocity.
+// Without outside forces or losses it keeps its length and direction.
+pub fn draw_momentum(center_of_mass: Vec2, velocity: Vec2) {
+    let center = world_to_screen(center_of_mass);
+    let tip = world_to_screen(center_of_mass + velocity * MOMENTUM_ARROW_TIME);
+    let arm = 6.0;
+    draw_line(
+        center.x - arm,
+        center.y,
+        center.x + arm,
+        center.y,
+        2.0,
+        MOMENTUM_COLOR,
+    );
+    draw_line(
+        center.x,
+        center.y - arm,
+        center.x,
+        center.y + arm,
+        2.0,
+        MOMENTUM_COLOR,
+    );
+
+    let shaft = tip - center;
+    if shaft.length() < arm {
+        return; // Too short to point anywhere
+    }
+    draw_line(center.x, center.y, tip.x, tip.y, 2.0, MOMENTUM_COLOR);
+    let back = -shaft.normalize() * 2.0 * arm;
+    for side in [
+        back.rotate(Vec2::from_angle(0.5)),
+        back.rotate(Vec2::from_angle(-0.5)),
+    ] {
+        draw_line(
+            tip.x,
+            tip.y,
+            tip.x + side.x,
+            tip.y + side.y,
+            2.0,
+            MOMENTUM_COLOR,
+        );
+    }
+}
+
+// === HUD ===
+pub const HUD_FONT_SIZE: f32 = 20.0;
+
+// Lines of text stacked down the top-left corner.
+pub fn draw_hud(lines: &[String]) {
+    for (i, line) in lines.iter().enumerate() {
+        let y = HUD_FONT_SIZE * (i as f32 + 1.0);
+        draw_text(line, 10.0, y, HUD_FONT_SIZE, WHITE);
+    }
+}
+
+// Lines of text stacked down the top-right corner, in yellow to stand apart from the HUD.
+pub fn draw_debug_overlay(lines: &[String]) {
+    for (i, line) in lines.iter().enumerate() {
+        let width = measure_text(line, None, HUD_FONT_SIZE as u16, 1.0).width;
+        let y = HUD_FONT_SIZE * (i as f32 + 1.0);
+        draw_text(
+            line,
+            screen_width() - width - 10.0,
+            y,
+            HUD_FONT_SIZE,
+            YELLOW,
+        );
+    }
+}
+
+const TEXT_BOX_PADDING: f32 = 6.0;
+
+// Size in pixels of the box draw_text_box would draw.
+fn text_box_size(lines: &[String]) -> Vec2 {
+    let width = lines
+        .iter()
+        .map(|line| measure_text(line, None, HUD_FONT_SIZE as u16, 1.0).width)
+        .fold(0.0, f32::max);
+    let height = HUD_FONT_SIZE * lines.len() as f32;
+    Vec2::new(width, height) + 2.0 * TEXT_BOX_PADDING
+}
+
+// Lines of text in a dark box with its top-left corner at `corner` (screen pixels).
+fn draw_text_box(lines: &[String], corner: Vec2) {
+    let size = text_box_size(lines);
+    draw_rectangle(
+        corner.x,
+        corner.y,
+        size.x,
+        size.y,
+        Color::new(0.0, 0.0, 0.0, 0.75),
+    );
+    for (i, line) in lines.iter().enumerate() {
+        let baseline = corner.y + TEXT_BOX_PADDING + HUD_FONT_SIZE * (i as f32 + 0.8);
+        draw_text(
+            line,
+            corner.x + TEXT_BOX_PADDING,
+            baseline,
+            HUD_FONT_SIZE,
+            WHITE,
+        );
+    }
+}
+
+// A text box just below and right of `anchor` (screen pixels), pushed back
+// on screen near the right and bottom edges.
+pub fn draw_tooltip(lines: &[String], anchor: Vec2) {
+    let size = text_box_size(lines);
+    let corner = (anchor + 16.0).min(Vec2::new(screen_width(), screen_height()) - size);
+    draw_text_box(lines, corner);
+}
+
+// A text box in the bottom-left corner.
+pub fn draw_panel(lines: &[String]) {
+    let size = text_box_size(lines);
+    draw_text_box(lines, Vec2::new(10.0, screen_height() - size.y - 10.0));
+}
+
+// === Screenshots ===
+// Writes what has been drawn so far this frame to a PNG. Like
+// Image::export_png, but reporting failure instead of panicking.
+pub fn save_screenshot(path: &str) -> image::ImageResult<()> {
+    let screen = get_screen_data();
+    let (width, height) = (screen.width as usize, screen.height as usize);
+
+    // The framebuffer's rows run bottom to top
+    let mut bytes = Vec::with_capacity(screen.bytes.len());
+    for row in screen.bytes.chunks_exact(width * 4).rev() {
+        bytes.extend_from_slice(row);
+    }
+    image::save_buffer(
+        path,
+        &bytes,
+        width as u32,
+        height as u32,
+        image::ColorType::Rgba8,
+    )
+}
+
+// === Explosion Rings ===
+pub const RING_DURATION: f32 = 0.4; // Seconds a ring takes to expand and fade
+
+// Feedback for an explosion: a circle growing to the blast radius.
+pub struct Ring {
+    pub center: Vec2,
+    pub radius: f32,
+    age: f32,
+}
+
+impl Ring {
+    pub fn new(center: Vec2, radius: f32) -> Self {
+        Self {
+            center,
+            radius,
+            age: 0.0,
+        }
+    }
+
+    // Ages the ring by dt of real time; false once it has finished.
+    pub fn advance(&mut self, dt: f32) -> bool {
+        self.age += dt;
+        self.age < RING_DURATION
+    }
+
+    pub fn draw(&self) {
+        let t = self.age / RING_DURATION;
+        let center = world_to_screen(self.center);
+        let radius = self.radius * t * pixels_per_meter();
+        let color = Color::new(1.0, 0.6, 0.1, 1.0 - t);
+        draw_circle_lines(center.x, center.y, radius, 3.0, color);
+    }
+}
@@ -0,0 +1,139 @@
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+
+use crate::boundary::Boundary;
+use crate::config::Config;
+use crate::particle::Particle;
+use crate::physics::Physics;
+
+pub const SCENE_PATH: &str = "scene.json";
+
+// === Scene Files ===
+#[derive(Serialize)]
+struct SceneRef<'a> {
+    physics: &'a Physics,
+    particles: &'a [Particle],
+}
+
+// What gets loaded back: a file saved with S, or one written by hand and
+// passed with --scene. Everything is optional in a hand-written scene.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct Scene {
+    pub sim_min_width: Option<f32>, // World width (m) across the narrower side of the window
+    pub physics: Physics,
+    pub particles: Vec<Particle>,
+}
+
+impl Scene {
+    // Reads TOML if the path ends in .toml, JSON otherwise.
+    pub fn from_file(path: &str) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        if path.ends_with(".toml") {
+            toml::from_str(&text).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+        } else {
+            Ok(serde_json::from_str(&text)?)
+        }
+    }
+
+    // Puts the scene's settings in place of the configured ones.
+    pub fn apply(self, config: &mut Config) {
+        if let Some(width) = self.sim_min_width {
+            config.sim_min_width = width;
+        }
+        config.physics = self.physics;
+        config.particles = self.particles;
+    }
+}
+
+// Makes sure every particle starts fully inside the boundary's rectangle,
+// naming the first one that doesn't.
+pub fn check_inside(particles: &[Particle], boundary: &Boundary) -> Result<(), String> {
+    for (i, p) in particles.iter().enumerate() {
+        let extents = p.shape.half_extents();
+        let inside = p.position.x - extents.x >= boundary.left
+            && p.position.x + extents.x <= boundary.right
+            && p.position.y - extents.y >= boundary.bottom
+            && p.position.y + extents.y <= boundary.top;
+        if !inside {
+            return Err(format!(
+                "particles[{i}] at ({}, {}) starts outside the boundary ({}..{}, {}..{})",
+                p.position.x,
+                p.position.y,
+                boundary.left,
+                boundary.right,
+                boundary.bottom,
+                boundary.top
+            ));
+        }
+    }
+    Ok(())
+}
+
+pub fn save_scene(path: &str, particles: &[Particle], physics: &Physics) -> io::Result<()> {
+    let json = serde_json::to_string_pretty(&SceneRef { physics, particles })?;
+    fs::write(path, json)
+}
+
+// Reads a saved scene and holds it to the same checks as one passed with
+// --scene: settings that pass Config::validate, and every particle inside
+// `boundary`. A scene failing either is rejected with the reason.
+pub fn load_scene(path: &str, boundary: &Boundary) -> Result<(Vec<Particle>, Physics), String> {
+    let scene = Scene::from_file(path).map_err(|err| err.to_string())?;
+    let mut config = Config::default();
+    scene.apply(&mut config);
+    config.validate()?;
+    check_inside(&config.particles, boundary)?;
+    Ok((config.particles, config.physics))
+}
+
+// macroquad's Color has no serde support, so store it as [r, g, b, a].
+pub mod color_format {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(color: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        [color.r, color.g, color.b, color.a].serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        let [r, g, b, a] = <[f32; 4]>::deserialize(deserializer)?;
+        Ok(Color::new(r, g, b, a))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scene_file(name: &str, particles: &[Particle], physics: &Physics) -> String {
+        let path = std::env::temp_dir().join(name);
+        let path = path.to_str().unwrap().to_string();
+        save_scene(&path, particles, physics).unwrap();
+        path
+    }
+
+    #[test]
+    fn load_rejects_what_scene_flag_rejects() {
+        let boundary = Boundary::from_dimensions(10.0, 10.0);
+        let inside = Particle::new(Vec2::new(5.0, 5.0), Vec2::ZERO, 0.5, 1.0, WHITE);
+        let outside = Particle::new(Vec2::new(20.0, 5.0), Vec2::ZERO, 0.5, 1.0, WHITE);
+        let bouncy = Physics {
+            restitution: 2.0,
+            ..Physics::default()
+        };
+
+        let path = scene_file(
+            "load_ok.json",
+            std::slice::from_ref(&inside),
+            &Physics::default(),
+        );
+        assert!(load_scene(&path, &boundary).is_ok());
+        let path = scene_file("load_outside.json", &[outside], &Physics::default());
+        assert!(load_scene(&path, &boundary).is_err());
+        let path = scene_file("load_invalid.json", &[inside], &bouncy);
+        assert!(load_scene(&path, &boundary).is_err());
+    }
+}
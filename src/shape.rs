@@ -0,0 +1,163 @@
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// === Shapes ===
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum Shape {
+    Circle { radius: f32 },
+    Aabb { half_extents: Vec2 }, // Axis-aligned box, never rotates
+}
+
+// Overlap between two shapes: `normal` points from the first toward the second.
+pub struct Contact {
+    pub normal: Vec2,
+    pub depth: f32,
+}
+
+impl Shape {
+    // Radius of the smallest circle around the center enclosing the shape.
+    pub fn bounding_radius(&self) -> f32 {
+        match *self {
+            Shape::Circle { radius } => radius,
+            Shape::Aabb { half_extents } => half_extents.length(),
+        }
+    }
+
+    pub fn area(&self) -> f32 {
+        match *self {
+            Shape::Circle { radius } => std::f32::consts::PI * radius * radius,
+            Shape::Aabb { half_extents } => 4.0 * half_extents.x * half_extents.y,
+        }
+    }
+
+    // Half the width and height of the shape's axis-aligned bounds.
+    pub fn half_extents(&self) -> Vec2 {
+        match *self {
+            Shape::Circle { radius } => Vec2::splat(radius),
+            Shape::Aabb { half_extents } => half_extents,
+        }
+    }
+
+    // How far the shape reaches from its center along a unit direction.
+    pub fn support(&self, direction: Vec2) -> f32 {
+        match *self {
+            Shape::Circle { radius } => radius,
+            Shape::Aabb { half_extents } => half_extents.dot(direction.abs()),
+        }
+    }
+
+    // Whether a point, given relative to the shape's center, lies inside it.
+    pub fn contains(&self, offset: Vec2) -> bool {
+        match *self {
+            Shape::Circle { radius } => offset.length() <= radius,
+            Shape::Aabb { half_extents } => {
+                offset.x.abs() <= half_extents.x && offset.y.abs() <= half_extents.y
+            }
+        }
+    }
+}
+
+pub fn contact(a: &Shape, a_pos: Vec2, b: &Shape, b_pos: Vec2) -> Option<Contact> {
+    match (*a, *b) {
+        (Shape::Circle { radius: ra }, Shape::Circle { radius: rb }) => {
+            let delta = b_pos - a_pos;
+            let distance = delta.length();
+            let min_dist = ra + rb;
+            if distance >= min_dist || distance == 0.0 {
+                return None;
+            }
+            Some(Contact {
+                normal: delta / distance,
+                depth: min_dist - distance,
+            })
+        }
+        (Shape::Aabb { half_extents: ha }, Shape::Aabb { half_extents: hb }) => {
+            let delta = b_pos - a_pos;
+            let overlap = ha + hb - delta.abs();
+            if overlap.x <= 0.0 || overlap.y <= 0.0 {
+                return None;
+            }
+            // Push apart along the axis of least penetration
+            Some(if overlap.x < overlap.y {
+                Contact {
+                    normal: Vec2::new(delta.x.signum(), 0.0),
+                    depth: overlap.x,
+                }
+            } else {
+                Contact {
+                    normal: Vec2::new(0.0, delta.y.signum()),
+                    depth: overlap.y,
+                }
+            })
+        }
+        (Shape::Circle { radius }, Shape::Aabb { half_extents }) => {
+            circle_box_contact(a_pos, radius, b_pos, half_extents).map(|c| Contact {
+                normal: -c.normal,
+                depth: c.depth,
+            })
+        }
+        (Shape::Aabb { half_extents }, Shape::Circle { radius }) => {
+            circle_box_contact(b_pos, radius, a_pos, half_extents)
+        }
+    }
+}
+
+// Contact with the normal pointing from the box toward the circle.
+fn circle_box_contact(
+    center: Vec2,
+    radius: f32,
+    box_pos: Vec2,
+    half_extents: Vec2,
+) -> Option<Contact> {
+    let local = center - box_pos;
+    let closest = local.clamp(-half_extents, half_extents);
+
+    if closest == local {
+        // Center is inside the box: leave through the nearest face
+        let inset = half_extents - local.abs();
+        return Some(if inset.x < inset.y {
+            Contact {
+                normal: Vec2::new(local.x.signum(), 0.0),
+                depth: radius + inset.x,
+            }
+        } else {
+            Contact {
+                normal: Vec2::new(0.0, local.y.signum()),
+                depth: radius + inset.y,
+            }
+        });
+    }
+
+    let offset = local - closest;
+    let distance = offset.length();
+    if distance >= radius {
+        return None;
+    }
+    Some(Contact {
+        normal: offset / distance,
+        depth: radius - distance,
+    })
+}
+
+// === Swept Circles ===
+// First fraction t in [0, 1] of a step at which two circles moving in straight
+// lines come within `distance` of each other, given the offset between their
+// centers at the start and end of the step. None if they were already touching
+// at the start (the discrete checks handle that) or never meet.
+pub fn time_of_impact(start_offset: Vec2, end_offset: Vec2, distance: f32) -> Option<f32> {
+    // Solve |start + t * motion|² = distance² for the earlier root
+    let motion = end_offset - start_offset;
+    let a = motion.length_squared();
+    let b = 2.0 * start_offset.dot(motion);
+    let c = start_offset.length_squared() - distance * distance;
+    if c <= 0.0 || a == 0.0 || b >= 0.0 {
+        return None;
+    }
+
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let t = (-b - discriminant.sqrt()) / (2.0 * a);
+    (t <= 1.0).then_some(t)
+}
@@ -0,0 +1,49 @@
+use macroquad::prelude::*;
+use std::ops::Range;
+
+use crate::particle::Particle;
+
+pub const SPAWN_SEED: u64 = 0x5EED; // Change for a different but still repeatable run
+pub const SPAWN_DENSITY: f32 = 3.0; // Areal density (kg/m²) of spawned particles
+
+// === Spawner ===
+// Source of every random choice made when adding particles. It carries its
+// own SplitMix64 generator, so the same seed and the same inputs always give
+// the same particles, whatever else uses macroquad's global RNG.
+pub struct Spawner {
+    state: u64,
+}
+
+impl Spawner {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in [min, max).
+    pub fn range(&mut self, range: Range<f32>) -> f32 {
+        // The top 24 bits fill an f32 mantissa exactly
+        let unit = (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32;
+        range.start + (range.end - range.start) * unit
+    }
+
+    // A circle with a random radius in the given range, mass from
+    // SPAWN_DENSITY so bigger means heavier, and a random bright color.
+    pub fn particle(&mut self, position: Vec2, velocity: Vec2, radius: Range<f32>) -> Particle {
+        let radius = self.range(radius);
+        let color = Color::new(
+            self.range(0.2..1.0),
+            self.range(0.2..1.0),
+            self.range(0.2..1.0),
+            1.0,
+        );
+        Particle::from_density(position, velocity, radius, SPAWN_DENSITY, color)
+    }
+}
@@ -0,0 +1,64 @@
+use macroquad::prelude::*;
+
+use crate::coords::world_to_screen;
+use crate::particle::Particle;
+use crate::render::DrawOptions;
+
+// === Springs ===
+// Hooke's-law link between two particles, referenced by index into the world's list.
+pub struct Spring {
+    pub a: usize,
+    pub b: usize,
+    pub rest_length: f32,
+    pub stiffness: f32, // N per meter of stretch
+    pub damping: f32,   // N per m/s of stretching speed
+}
+
+impl Spring {
+    pub fn new(a: usize, b: usize, rest_length: f32, stiffness: f32, damping: f32) -> Self {
+        Self {
+            a,
+            b,
+            rest_length,
+            stiffness,
+            damping,
+        }
+    }
+
+    // Pulls both ends toward the rest length, damping motion along the spring.
+    // Springs whose particles no longer exist are skipped.
+    pub fn apply(&self, particles: &mut [Particle]) {
+        let (Some(pa), Some(pb)) = (particles.get(self.a), particles.get(self.b)) else {
+            return;
+        };
+
+        let delta = pb.position - pa.position;
+        let length = delta.length();
+        if length == 0.0 {
+            return;
+        }
+
+        // Springs move both ends together, so one moving end wakes the other
+        if pa.is_sleeping() != pb.is_sleeping() {
+            particles[self.a].wake();
+            particles[self.b].wake();
+        }
+        let (pa, pb) = (&particles[self.a], &particles[self.b]);
+
+        let direction = delta / length;
+        let stretch_speed = (pb.velocity - pa.velocity).dot(direction);
+        let tension = self.stiffness * (length - self.rest_length) + self.damping * stretch_speed;
+        let force = direction * tension;
+
+        particles[self.a].apply_force(force);
+        particles[self.b].apply_force(-force);
+    }
+
+    pub fn draw(&self, particles: &[Particle], options: &DrawOptions) {
+        if let (Some(pa), Some(pb)) = (particles.get(self.a), particles.get(self.b)) {
+            let a = world_to_screen(pa.interpolated_position(options.interpolation));
+            let b = world_to_screen(pb.interpolated_position(options.interpolation));
+            draw_line(a.x, a.y, b.x, b.y, 2.0, LIGHTGRAY);
+        }
+    }
+}
@@ -0,0 +1,1608 @@
+use macroquad::prelude::*;
+use std::collections::HashSet;
+use std::ops::Range;
+
+use crate::attractor::PointAttractor;
+use crate::boundary::{Boundary, BoundaryShape};
+use crate::broad_phase::{BroadPhase, QuadTree, SpatialHash};
+use crate::emitter::Emitter;
+use crate::force::{Drag, Force, Gravity};
+use crate::heatmap::Heatmap;
+use crate::obstacle::{StaticCircle, Wall};
+use crate::particle::{Particle, resolve_contact, sweep_particle_collision};
+use crate::physics::Physics;
+use crate::recorder::Recorder;
+use crate::render::{DrawOptions, draw_grid, draw_momentum};
+use crate::shape::contact;
+use crate::spawner::{SPAWN_SEED, Spawner};
+use crate::spring::Spring;
+
+// === World ===
+// All simulation state. Stepping never touches the window, so a world can be
+// driven headless as long as its boundary was sized explicitly.
+pub struct World {
+    pub particles: Vec<Particle>,
+    pub physics: Physics,
+    pub boundary: Boundary,
+    pub obstacles: Vec<StaticCircle>,
+    pub walls: Vec<Wall>,
+    pub springs: Vec<Spring>,
+    pub emitters: Vec<Emitter>,
+    pub attractors: Vec<PointAttractor>,
+    pub forces: Vec<Box<dyn Force>>, // Extra forces on top of the configured ones
+    pub spawner: Spawner,
+    pub broad_phase: BroadPhase,
+    pub recorder: Option<Recorder>, // Logs every step's particles to CSV while set
+    pub heatmap: Option<Heatmap>,   // Counts collisions per cell while set
+    trails_enabled: bool,
+    spatial_hash: SpatialHash,
+    conditions: Option<Conditions>, // As of the last step, to wake sleepers on change
+    collision_events: Vec<CollisionEvent>, // From the last step, reused between steps
+    touching: HashSet<(usize, usize)>, // Pairs in contact during the last step
+    touching_before: HashSet<(usize, usize)>, // The step before's, kept to reuse its storage
+    pairs: Vec<(usize, usize)>,     // Broad phase candidates, refilled each time they're needed
+    accumulator: f32,               // Simulated time (s) banked from frames but not stepped yet
+    hash_current: bool,             // Whether spatial_hash matches the particles as they are
+}
+
+// A hit during a step, for whoever drives the world to react to: sounds,
+// scores, counting impacts. Only real hits count, where the two met faster
+// than velocity_threshold; contacts merely resting on each other or on the
+// boundary never do, however long gravity presses them together. Two
+// particles already touching the step before haven't newly hit either, even
+// if the solver is still working off the last bit of their impact. Indices
+// are as they stood during the step, before any expired particles were
+// removed at its end.
+#[derive(Clone, Copy, Debug)]
+pub enum CollisionEvent {
+    Particles {
+        a: usize,
+        b: usize,
+        impulse: f32, // Normal impulse (N·s) each gave the other
+        point: Vec2,
+    },
+    Boundary {
+        particle: usize,
+        impulse: f32, // Normal impulse (N·s) the container pushed back with
+        point: Vec2,
+    },
+}
+
+impl CollisionEvent {
+    pub fn impulse(&self) -> f32 {
+        match *self {
+            Self::Particles { impulse, .. } | Self::Boundary { impulse, .. } => impulse,
+        }
+    }
+
+    pub fn point(&self) -> Vec2 {
+        match *self {
+            Self::Particles { point, .. } | Self::Boundary { point, .. } => point,
+        }
+    }
+
+    pub fn involves(&self, index: usize) -> bool {
+        match *self {
+            Self::Particles { a, b, .. } => a == index || b == index,
+            Self::Boundary { particle, .. } => particle == index,
+        }
+    }
+}
+
+const SCATTER_ATTEMPTS: usize = 100; // Random positions tried per particle before giving up
+const MAX_FRAME_TIME: f32 = 0.25; // Real seconds a single frame may add to the accumulator
+
+// Whatever acts on resting particles from outside; if any of it changes they
+// all have to wake up and respond.
+type Conditions = (Vec2, Vec2, f32, BoundaryShape);
+
+impl World {
+    pub fn new(physics: Physics, boundary: Boundary, particles: Vec<Particle>) -> Self {
+        let mut world = Self {
+            particles: Vec::new(),
+            physics,
+            boundary,
+            obstacles: Vec::new(),
+            walls: Vec::new(),
+            springs: Vec::new(),
+            emitters: Vec::new(),
+            attractors: Vec::new(),
+            forces: Vec::new(),
+            spawner: Spawner::new(SPAWN_SEED),
+            broad_phase: BroadPhase::Grid,
+            recorder: None,
+            heatmap: None,
+            trails_enabled: true,
+            spatial_hash: SpatialHash::new(),
+            conditions: None,
+            collision_events: Vec::new(),
+            touching: HashSet::new(),
+            touching_before: HashSet::new(),
+            pairs: Vec::new(),
+            accumulator: 0.0,
+            hash_current: false,
+        };
+        world.replace_particles(particles);
+        world
+    }
+
+    // Swaps in a new set of particles, such as ones loaded from a file,
+    // which don't know where they were a step ago until placed like this.
+    pub fn replace_particles(&mut self, mut particles: Vec<Particle>) {
+        for p in &mut particles {
+            p.move_to(p.position);
+        }
+        self.particles = particles;
+        self.touching.clear();
+        self.hash_current = false;
+    }
+
+    // Advances the simulation by dt and returns the indices (before removal,
+    // highest first) of particles that expired during the step. The hits it
+    // resolved are left in collision_events until the next step.
+    pub fn step(&mut self, dt: f32) -> Vec<usize> {
+        self.collision_events.clear();
+        self.hash_current = false;
+
+        // A tumbling box turns its gravity a little every step
+        if self.physics.gravity_spin != 0.0 {
+            let turn = Vec2::from_angle(self.physics.gravity_spin * dt);
+            self.physics.gravity = turn.rotate(self.physics.gravity);
+        }
+
+        let conditions = (
+            self.physics.gravity,
+            self.physics.wind,
+            self.physics.gravitational_constant,
+            self.boundary.shape,
+        );
+        if self.conditions != Some(conditions) {
+            self.wake_all();
+            self.conditions = Some(conditions);
+        }
+
+        // New particles from any running emitters
+        for emitter in &mut self.emitters {
+            emitter.emit(&mut self.particles, &mut self.spawner, dt);
+        }
+
+        // Velocity Verlet moves the particles before their forces are worked out
+        for p in &mut self.particles {
+            p.begin_update(&self.physics, dt);
+        }
+
+        // Gravity, wind, drag, attractors and any extra forces
+        self.apply_forces();
+
+        // Mutual attraction between particles
+        if self.physics.gravitational_constant != 0.0 {
+            self.apply_mutual_gravity();
+        }
+
+        // Spring forces
+        for spring in &self.springs {
+            spring.apply(&mut self.particles);
+        }
+
+        // Integrate the rest of the way, now every force is in
+        for p in &mut self.particles {
+            p.finish_update(&self.physics, dt);
+        }
+
+        // Continuous collisions first, so fast particles stop where their
+        // paths first touched something instead of passing through it. The
+        // boundary needs no sweep: it already pulls back any particle that
+        // ends a step beyond it.
+        if self.physics.ccd {
+            self.sweep_collisions();
+        }
+
+        // Contacts, solved several times over so pushes travel through a pile
+        // instead of stopping at the first one. Each pass settles the pairs and
+        // then holds everything out of the pegs, walls and boundary, so a
+        // stack pressed into the floor is pushed back up within the step.
+        // Only the first pass bounces particles off the boundary and reports
+        // hits; later ones just refine the same contacts.
+        self.refill_candidate_pairs(0.0);
+        std::mem::swap(&mut self.touching, &mut self.touching_before);
+        self.touching.clear();
+        for iteration in 0..self.physics.solver_iterations {
+            let first = iteration == 0;
+            for &(i, j) in &self.pairs {
+                let (left, right) = self.particles.split_at_mut(j);
+                let (p1, p2) = (&mut left[i], &mut right[0]);
+                let Some(contact) = contact(&p1.shape, p1.position, &p2.shape, p2.position) else {
+                    continue;
+                };
+                let hit = resolve_contact(p1, p2, contact, &self.physics, dt);
+                if !first {
+                    continue;
+                }
+                self.touching.insert((i, j));
+                if let Some(impulse) = hit
+                    && !self.touching_before.contains(&(i, j))
+                {
+                    let reach = p1.radius() / (p1.radius() + p2.radius());
+                    self.collision_events.push(CollisionEvent::Particles {
+                        a: i,
+                        b: j,
+                        impulse,
+                        point: p1.position.lerp(p2.position, reach),
+                    });
+                }
+            }
+
+            for (i, p) in self.particles.iter_mut().enumerate() {
+                if p.is_immovable() {
+                    continue;
+                }
+                for obstacle in &self.obstacles {
+                    p.handle_obstacle_collision(&self.physics, obstacle);
+                }
+                for wall in &self.walls {
+                    p.handle_wall_collision(&self.physics, wall);
+                }
+                if !first {
+                    p.hold_inside(&self.boundary);
+                    continue;
+                }
+                let impulse = p.handle_boundary_collision(&self.physics, &self.boundary);
+                if let Some(normal) = impulse.try_normalize() {
+                    self.collision_events.push(CollisionEvent::Boundary {
+                        particle: i,
+                        impulse: impulse.length(),
+                        point: p.position - normal * p.shape.support(-normal),
+                    });
+                }
+            }
+        }
+
+        if self.trails_enabled {
+            for p in self.particles.iter_mut().filter(|p| !p.is_immovable()) {
+                p.record_trail();
+            }
+        }
+
+        if let Some(heatmap) = &mut self.heatmap {
+            for event in &self.collision_events {
+                heatmap.record(event.point());
+            }
+            heatmap.decay(dt);
+        }
+
+        for p in &mut self.particles {
+            p.update_sleep(&self.physics);
+        }
+
+        // Drop particles that outlived their lifetime
+        let expired: Vec<usize> = (0..self.particles.len())
+            .rev()
+            .filter(|&i| self.particles[i].is_expired())
+            .collect();
+        for &i in &expired {
+            self.remove_particle(i);
+        }
+
+        if let Some(recorder) = &mut self.recorder
+            && let Err(err) = recorder.record(&self.particles)
+        {
+            eprintln!("stopped recording trajectories: {err}");
+            self.recorder = None;
+        }
+        expired
+    }
+
+    // Banks a frame's real time, scaled by `time_scale`, and runs as many
+    // steps of `dt` as it pays for, leaving the remainder banked for the next
+    // frame. `step` runs each one, with whatever has to be applied first. A
+    // hitch or a minimized window can report a frame lasting seconds, so
+    // anything past MAX_FRAME_TIME is simply lost: the simulation runs slow
+    // for that frame rather than jumping or freezing. Catching up could still
+    // take longer than the stall itself, so past `max_steps` the rest is
+    // dropped too. Gives back how many steps ran.
+    pub fn advance(
+        &mut self,
+        frame_time: f32,
+        time_scale: f32,
+        dt: f32,
+        max_steps: u32,
+        mut step: impl FnMut(&mut Self),
+    ) -> u32 {
+        self.accumulator += frame_time.min(MAX_FRAME_TIME) * time_scale;
+        let mut steps = 0;
+        while self.accumulator >= dt {
+            if steps >= max_steps {
+                eprintln!(
+                    "warning: {steps} physics steps in one frame, dropping {:.3} s of simulated time",
+                    self.accumulator
+                );
+                self.accumulator = 0.0;
+                break;
+            }
+            step(self);
+            self.accumulator -= dt;
+            steps += 1;
+        }
+        steps
+    }
+
+    // Time banked toward the next step, for drawing between steps.
+    pub fn accumulator(&self) -> f32 {
+        self.accumulator
+    }
+
+    // Forgets banked time, so none is caught up on later.
+    pub fn clear_accumulator(&mut self) {
+        self.accumulator = 0.0;
+    }
+
+    // Refills `pairs` with the ones that may be within `margin` of touching,
+    // from the selected broad phase.
+    fn refill_candidate_pairs(&mut self, margin: f32) {
+        self.pairs.clear();
+        match self.broad_phase {
+            BroadPhase::Grid => {
+                self.spatial_hash.rebuild(&self.particles, margin);
+                self.spatial_hash.candidate_pairs_into(&mut self.pairs);
+            }
+            BroadPhase::QuadTree => QuadTree::from_particles(&self.particles, margin)
+                .candidate_pairs_into(&self.particles, margin, &mut self.pairs),
+        }
+    }
+
+    fn sweep_collisions(&mut self) {
+        // Two paths that met can end up to both displacements further apart
+        // than touching, so widen the cells enough to still pair them
+        let max_displacement = self
+            .particles
+            .iter()
+            .map(|p| p.displacement())
+            .fold(0.0, f32::max);
+        self.refill_candidate_pairs(2.0 * max_displacement);
+        for &(i, j) in &self.pairs {
+            let (left, right) = self.particles.split_at_mut(j);
+            sweep_particle_collision(&mut left[i], &mut right[0], &self.physics);
+        }
+
+        for p in self.particles.iter_mut().filter(|p| !p.is_immovable()) {
+            for obstacle in &self.obstacles {
+                p.sweep_obstacle_collision(&self.physics, obstacle);
+            }
+            for wall in &self.walls {
+                p.sweep_wall_collision(&self.physics, wall);
+            }
+        }
+    }
+
+    // Sums what every force gives each particle and applies it as a force,
+    // for the integrator to turn back into acceleration. Gravity, wind and
+    // drag come from the physics settings, so they follow any change to them.
+    fn apply_forces(&mut self) {
+        let gravity = Gravity {
+            acceleration: self.physics.gravity,
+        };
+        let wind = Gravity {
+            acceleration: self.physics.wind,
+        };
+        let drag = Drag {
+            quadratic: self.physics.drag_coefficient,
+            linear: self.physics.drag,
+        };
+        let forces: Vec<&dyn Force> = [&gravity as &dyn Force, &wind, &drag]
+            .into_iter()
+            .chain(self.attractors.iter().map(|a| a as &dyn Force))
+            .chain(self.forces.iter().map(|f| f.as_ref()))
+            .collect();
+
+        let particles = &self.particles;
+        let accelerations: Vec<Vec2> = particles
+            .iter()
+            .map(|p| forces.iter().map(|f| f.apply(p, particles)).sum())
+            .collect();
+        for (p, acceleration) in self.particles.iter_mut().zip(accelerations) {
+            p.apply_force(acceleration * p.mass);
+        }
+    }
+
+    fn apply_mutual_gravity(&mut self) {
+        #[cfg(not(feature = "parallel"))]
+        let forces = self.mutual_gravity();
+        #[cfg(feature = "parallel")]
+        let forces = self.mutual_gravity_parallel();
+        for (p, force) in self.particles.iter_mut().zip(forces) {
+            p.apply_force(force);
+        }
+    }
+
+    // Net pull on each particle from all the others, a pair at a time. Each
+    // particle's total adds up its pulls in index order, as the parallel
+    // version below does too, so both give the same forces bit for bit.
+    #[cfg(any(test, not(feature = "parallel")))]
+    fn mutual_gravity(&self) -> Vec<Vec2> {
+        let g = self.physics.gravitational_constant;
+        let softening_sq = self.physics.gravity_softening * self.physics.gravity_softening;
+
+        let mut forces = vec![Vec2::ZERO; self.particles.len()];
+        for (j, pj) in self.particles.iter().enumerate() {
+            for (i, pi) in self.particles[..j].iter().enumerate() {
+                let force = pull(pi, pj, g, softening_sq);
+                forces[i] += force;
+                forces[j] -= force;
+            }
+        }
+        forces
+    }
+
+    // The same forces, each particle's total summed on its own thread from
+    // every other particle in index order. Every pair's pull is found twice,
+    // once for each side, but no total depends on how the work was split.
+    #[cfg(feature = "parallel")]
+    fn mutual_gravity_parallel(&self) -> Vec<Vec2> {
+        use rayon::prelude::*;
+
+        let g = self.physics.gravitational_constant;
+        let softening_sq = self.physics.gravity_softening * self.physics.gravity_softening;
+
+        let particles = &self.particles;
+        (0..particles.len())
+            .into_par_iter()
+            .map(|i| {
+                let pi = &particles[i];
+                let mut total = Vec2::ZERO;
+                for (j, pj) in particles.iter().enumerate() {
+                    if j < i {
+                        total -= pull(pj, pi, g, softening_sq);
+                    } else if j > i {
+                        total += pull(pi, pj, g, softening_sq);
+                    }
+                }
+                total
+            })
+            .collect()
+    }
+
+    pub fn wake_all(&mut self) {
+        for p in &mut self.particles {
+            p.wake();
+        }
+    }
+
+    pub fn sleeping_count(&self) -> usize {
+        self.particles.iter().filter(|p| p.is_sleeping()).count()
+    }
+
+    // Pulls every particle toward `center` for the next step (pushes it away
+    // if `strength` is negative). The acceleration is `strength` at the
+    // center and halves at `falloff` meters, the same for every mass.
+    pub fn apply_point_force(&mut self, center: Vec2, strength: f32, falloff: f32) {
+        for p in &mut self.particles {
+            let delta = center - p.position;
+            let distance_sq = delta.length_squared();
+            let acceleration = strength / (1.0 + distance_sq / (falloff * falloff));
+            p.wake();
+            p.apply_force(delta.normalize_or_zero() * acceleration * p.mass);
+        }
+    }
+
+    // Indices, in order, of the particles whose centers lie within `radius`
+    // of `center`, edge included. Looks them up in the collision grid, rebuilt
+    // first if anything has moved since it last was, so further queries before
+    // the next step come cheap. A radius spanning more cells than there are
+    // particles is quicker to answer by checking each one.
+    pub fn query_radius(&mut self, center: Vec2, radius: f32) -> Vec<usize> {
+        // Particles pushed in directly since the rebuild show up in the count
+        if !self.hash_current || self.spatial_hash.len() != self.particles.len() {
+            self.spatial_hash.rebuild(&self.particles, 0.0);
+            self.hash_current = true;
+        }
+        if self.spatial_hash.cells_within(center, radius) > self.particles.len() {
+            return (0..self.particles.len())
+                .filter(|&i| self.particles[i].position.distance_squared(center) <= radius * radius)
+                .collect();
+        }
+        self.spatial_hash
+            .query_radius(&self.particles, center, radius)
+    }
+
+    // Kicks particle `index` with `impulse` (N·s, i.e. kg·m/s): its velocity
+    // changes by impulse / mass at once. A sleeper wakes; pinned ones ignore it.
+    pub fn apply_impulse(&mut self, index: usize, impulse: Vec2) {
+        self.particles[index].apply_impulse(impulse);
+    }
+
+    // Kicks every particle within `radius` of `center` straight away from it
+    // (toward it for negative strength) with an impulse of `strength / distance`
+    // N·s, so `strength` is the impulse 1 m out and heavier particles pick up
+    // less speed. The distance is floored at the particle's own radius so one
+    // right at the center isn't flung off to infinity.
+    pub fn apply_radial_impulse(&mut self, center: Vec2, radius: f32, strength: f32) {
+        for i in self.query_radius(center, radius) {
+            let p = &self.particles[i];
+            let delta = p.position - center;
+            let distance = delta.length();
+            let direction = delta.try_normalize().unwrap_or(Vec2::Y);
+            let impulse = direction * strength / distance.max(p.radius());
+            self.apply_impulse(i, impulse);
+        }
+    }
+
+    // Adds up to `count` particles from the spawner at random spots inside
+    // the boundary, rejecting any spot that overlaps a particle, peg or wall
+    // already there. Returns how many fit; a crowded world can take fewer.
+    pub fn scatter_particles(&mut self, count: usize, radius: Range<f32>) -> usize {
+        let mut placed = 0;
+        for _ in 0..count {
+            let mut particle = self
+                .spawner
+                .particle(Vec2::ZERO, Vec2::ZERO, radius.clone());
+            let r = particle.radius();
+            let free_spot = (0..SCATTER_ATTEMPTS).find_map(|_| {
+                let b = &self.boundary;
+                let position = Vec2::new(
+                    self.spawner.range(b.left + r..b.right - r),
+                    self.spawner.range(b.bottom + r..b.top - r),
+                );
+                let clear = b.clamp(position, r) == position
+                    && self
+                        .particles
+                        .iter()
+                        .all(|p| p.position.distance(position) >= p.radius() + r)
+                    && self
+                        .obstacles
+                        .iter()
+                        .all(|o| o.position.distance(position) >= o.radius + r)
+                    && self
+                        .walls
+                        .iter()
+                        .all(|w| w.closest_point(position).distance(position) >= r);
+                clear.then_some(position)
+            });
+            let Some(position) = free_spot else {
+                break;
+            };
+            particle.move_to(position);
+            self.particles.push(particle);
+            placed += 1;
+        }
+        placed
+    }
+
+    // Removes a particle, dropping springs attached to it and re-pointing the
+    // rest at the shifted indices. Everything resting on it, directly or
+    // through others, is woken to fall into the gap.
+    pub fn remove_particle(&mut self, index: usize) -> Particle {
+        self.wake_touching(index);
+        self.hash_current = false;
+        let shift = |i: usize| if i > index { i - 1 } else { i };
+        self.touching_before.clear();
+        self.touching_before.extend(
+            self.touching
+                .iter()
+                .filter(|&&(a, b)| a != index && b != index)
+                .map(|&(a, b)| (shift(a), shift(b))),
+        );
+        std::mem::swap(&mut self.touching, &mut self.touching_before);
+        self.springs.retain(|s| s.a != index && s.b != index);
+        for spring in &mut self.springs {
+            if spring.a > index {
+                spring.a -= 1;
+            }
+            if spring.b > index {
+                spring.b -= 1;
+            }
+        }
+        self.particles.remove(index)
+    }
+
+    // Wakes every particle in contact with `index` as of the last step, and
+    // every particle in contact with those, and so on through the pile.
+    fn wake_touching(&mut self, index: usize) {
+        let mut reached = HashSet::from([index]);
+        let mut pending = vec![index];
+        while let Some(k) = pending.pop() {
+            for &(a, b) in &self.touching {
+                let other = match (a == k, b == k) {
+                    (true, _) => b,
+                    (_, true) => a,
+                    _ => continue,
+                };
+                if reached.insert(other) {
+                    self.particles[other].wake();
+                    pending.push(other);
+                }
+            }
+        }
+    }
+
+    // Σ ½·m·v² over all particles (J).
+    pub fn kinetic_energy(&self) -> f32 {
+        self.particles
+            .iter()
+            .map(|p| 0.5 * p.mass * p.velocity.length_squared())
+            .sum()
+    }
+
+    // Kinetic (including spin) plus gravitational potential energy: -m·g·r for
+    // uniform gravity, measured from the boundary's bottom-left corner, and
+    // -G·mᵢ·mⱼ/√(r² + ε²) for each pair under mutual gravity. Springs' stored
+    // energy isn't counted.
+    pub fn mechanical_energy(&self) -> f32 {
+        let origin = Vec2::new(self.boundary.left, self.boundary.bottom);
+        let mut energy: f32 = self
+            .particles
+            .iter()
+            .map(|p| {
+                let inverse_inertia = p.inverse_inertia();
+                let spin = if inverse_inertia > 0.0 {
+                    0.5 * p.angular_velocity * p.angular_velocity / inverse_inertia
+                } else {
+                    0.0
+                };
+                let offset = p.position - origin;
+                0.5 * p.mass * p.velocity.length_squared() + spin
+                    - p.mass * self.physics.gravity.dot(offset)
+            })
+            .sum();
+
+        let g = self.physics.gravitational_constant;
+        if g != 0.0 {
+            let softening_sq = self.physics.gravity_softening * self.physics.gravity_softening;
+            for (j, pj) in self.particles.iter().enumerate() {
+                for pi in &self.particles[..j] {
+                    let dist_sq = pi.position.distance_squared(pj.position) + softening_sq;
+                    energy -= g * pi.mass * pj.mass / dist_sq.sqrt();
+                }
+            }
+        }
+        energy
+    }
+
+    // Σ m·v over all particles (kg·m/s).
+    pub fn momentum(&self) -> Vec2 {
+        self.particles.iter().map(|p| p.momentum()).sum()
+    }
+
+    // Σ m·r / Σ m, or None with nothing (massive) in the world.
+    pub fn center_of_mass(&self) -> Option<Vec2> {
+        let total_mass: f32 = self.particles.iter().map(|p| p.mass).sum();
+        if total_mass <= 0.0 {
+            return None;
+        }
+        let weighted: Vec2 = self.particles.iter().map(|p| p.position * p.mass).sum();
+        Some(weighted / total_mass)
+    }
+
+    // Every hit during the last step, in the order they were resolved.
+    pub fn collision_events(&self) -> &[CollisionEvent] {
+        &self.collision_events
+    }
+
+    pub fn trails_enabled(&self) -> bool {
+        self.trails_enabled
+    }
+
+    // Turning trails off also wipes them, so they start fresh when turned back on.
+    pub fn set_trails_enabled(&mut self, enabled: bool) {
+        self.trails_enabled = enabled;
+        if !enabled {
+            for p in &mut self.particles {
+                p.clear_trail();
+            }
+        }
+    }
+
+    pub fn draw(&self, options: &DrawOptions) {
+        if options.grid {
+            draw_grid();
+        }
+        if let Some(heatmap) = &self.heatmap {
+            heatmap.draw();
+        }
+        self.boundary.draw();
+        for obstacle in &self.obstacles {
+            obstacle.draw();
+        }
+        for wall in &self.walls {
+            wall.draw();
+        }
+        for emitter in &self.emitters {
+            emitter.draw();
+        }
+        for attractor in &self.attractors {
+            attractor.draw();
+        }
+        for p in &self.particles {
+            p.draw_trail();
+        }
+        for spring in &self.springs {
+            spring.draw(&self.particles, options);
+        }
+        for p in &self.particles {
+            p.draw(options);
+        }
+        if options.momentum
+            && let Some(center) = self.center_of_mass()
+        {
+            let total_mass: f32 = self.particles.iter().map(|p| p.mass).sum();
+            draw_momentum(center, self.momentum() / total_mass);
+        }
+    }
+}
+
+// Newtonian attraction F = G * m1 * m2 / r² pulling `pi` toward `pj`, with r
+// softened so near-coincident particles don't receive enormous kicks.
+fn pull(pi: &Particle, pj: &Particle, g: f32, softening_sq: f32) -> Vec2 {
+    let delta = pj.position - pi.position;
+    let dist_sq = delta.length_squared() + softening_sq;
+    delta * (g * pi.mass * pj.mass / (dist_sq * dist_sq.sqrt()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MAX_STEPS_PER_FRAME;
+    use crate::coords::{SIM_MIN_WIDTH, WorldConfig, set_world};
+    use crate::material::{ICE, Material, RUBBER};
+    use crate::physics::Integrator;
+
+    const DT: f32 = 1.0 / 60.0;
+
+    fn ball(position: Vec2, velocity: Vec2) -> Particle {
+        Particle::new(position, velocity, 0.5, 1.0, WHITE)
+    }
+
+    fn open_box() -> Boundary {
+        Boundary::from_dimensions(20.0, 20.0)
+    }
+
+    // Balls stacked straight up from the floor, each just touching the next.
+    fn stack(count: usize, physics: Physics) -> World {
+        let particles = (0..count)
+            .map(|k| ball(Vec2::new(10.0, 1.5 + k as f32), Vec2::ZERO))
+            .collect();
+        World::new(physics, open_box(), particles)
+    }
+
+    fn deepest_overlap(world: &World) -> f32 {
+        let particles = &world.particles;
+        let mut deepest = 0.0f32;
+        for (j, pj) in particles.iter().enumerate() {
+            for pi in &particles[..j] {
+                let overlap = pi.radius() + pj.radius() - pi.position.distance(pj.position);
+                deepest = deepest.max(overlap);
+            }
+        }
+        deepest
+    }
+
+    #[test]
+    fn more_solver_iterations_give_a_firmer_stack() {
+        let settled = |solver_iterations| {
+            let physics = Physics {
+                solver_iterations,
+                ..Physics::default()
+            };
+            let mut world = stack(5, physics);
+            for _ in 0..600 {
+                world.step(DT);
+            }
+            deepest_overlap(&world)
+        };
+        let (one, two, four) = (settled(1), settled(2), settled(4));
+        assert!(
+            one > two && two > four,
+            "settled penetration {one} m with 1 pass, {two} m with 2, {four} m with 4"
+        );
+    }
+
+    // Nothing that loses energy on purpose: perfectly elastic, no friction
+    // or drag, and no threshold stopping slow bounces.
+    fn lossless(integrator: Integrator) -> Physics {
+        Physics {
+            restitution: 1.0,
+            friction: 1.0,
+            contact_friction: 0.0,
+            drag_coefficient: 0.0,
+            velocity_threshold: 0.0,
+            sleep_steps: 0,
+            integrator,
+            ..Physics::default()
+        }
+    }
+
+    // A ball dropped from 7 m onto the floor of a 10 m box, left to bounce.
+    fn bouncing_ball(integrator: Integrator) -> World {
+        let boundary = Boundary::from_dimensions(10.0, 10.0);
+        let particles = vec![ball(Vec2::new(5.0, 7.0), Vec2::ZERO)];
+        World::new(lossless(integrator), boundary, particles)
+    }
+
+    #[test]
+    fn verlet_bounce_drifts_less_energy_than_euler() {
+        let drift = |integrator| {
+            let mut world = bouncing_ball(integrator);
+            let initial = world.mechanical_energy();
+            for _ in 0..600 {
+                world.step(DT);
+            }
+            (world.mechanical_energy() - initial).abs()
+        };
+        let euler = drift(Integrator::Euler);
+        for verlet in [Integrator::Verlet, Integrator::PositionVerlet] {
+            let drifted = drift(verlet);
+            assert!(
+                drifted < euler / 10.0,
+                "over 600 steps Euler drifted {euler} J, {verlet:?} {drifted} J"
+            );
+        }
+    }
+
+    #[test]
+    fn verlet_keeps_a_frictionless_bouncing_ball_bouncing() {
+        let mut world = bouncing_ball(Integrator::Verlet);
+        let initial = world.mechanical_energy();
+        let mut worst = 0.0f32;
+        for _ in 0..3000 {
+            world.step(DT);
+            worst = worst.max((world.mechanical_energy() / initial - 1.0).abs());
+        }
+        assert!(
+            worst < 0.001,
+            "energy drifted {}% over 3000 steps",
+            worst * 100.0
+        );
+    }
+
+    // Five balls dropped with gravity pointing straight into the bottom-left
+    // corner, so they pile up against both walls at once.
+    fn corner_pile() -> World {
+        let physics = Physics {
+            gravity: Vec2::new(-6.93, -6.93),
+            ..Physics::default()
+        };
+        let particles = (0..5)
+            .map(|k| {
+                let k = k as f32;
+                ball(Vec2::new(1.8 + 0.3 * k, 2.0 + 1.2 * k), Vec2::ZERO)
+            })
+            .collect();
+        World::new(physics, open_box(), particles)
+    }
+
+    #[test]
+    fn particles_dropped_into_a_corner_settle_within_the_slop() {
+        let mut world = corner_pile();
+        for _ in 0..1200 {
+            world.step(DT);
+        }
+
+        let corner = Vec2::new(world.boundary.left, world.boundary.bottom);
+        assert!(
+            world
+                .particles
+                .iter()
+                .all(|p| p.position.distance(corner) < 5.0)
+        );
+        let slop = world.physics.correction_slop;
+        let overlap = deepest_overlap(&world);
+        assert!(overlap < slop, "overlap of {overlap} m left, slop {slop} m");
+    }
+
+    #[test]
+    fn awake_stack_of_five_holds_within_the_slop_without_drifting() {
+        // Kept awake, so sleep can't hide any creep
+        let physics = Physics {
+            sleep_steps: 0,
+            ..Physics::default()
+        };
+        let mut world = stack(5, physics);
+        for _ in 0..300 {
+            world.step(DT);
+        }
+
+        let settled: Vec<Vec2> = world.particles.iter().map(|p| p.position).collect();
+        let slop = world.physics.correction_slop;
+        for step in 0..300 {
+            world.step(DT);
+            let overlap = deepest_overlap(&world);
+            assert!(
+                overlap < slop,
+                "overlap of {overlap} m at step {step}, slop {slop} m"
+            );
+        }
+        for (p, before) in world.particles.iter().zip(settled) {
+            assert!(
+                p.position.distance(before) < 1e-3,
+                "ball drifted from {before} to {}",
+                p.position
+            );
+        }
+    }
+
+    #[test]
+    fn stepping_a_settled_pile_reuses_its_contact_buffers() {
+        let mut world = stack(5, Physics::default());
+        for _ in 0..10 {
+            world.step(DT);
+        }
+        let pairs = world.pairs.as_ptr();
+        let capacity = (world.touching.capacity(), world.touching_before.capacity());
+        assert!(!world.touching.is_empty());
+        for _ in 0..100 {
+            world.step(DT);
+        }
+        assert_eq!(world.pairs.as_ptr(), pairs);
+        assert_eq!(
+            (world.touching.capacity(), world.touching_before.capacity()),
+            capacity
+        );
+    }
+
+    #[test]
+    fn stack_of_five_boxes_rests_quietly() {
+        let particles = (0..5)
+            .map(|k| {
+                let position = Vec2::new(10.0, 1.5 + 1.05 * k as f32);
+                Particle::new_box(position, Vec2::ZERO, Vec2::splat(0.5), 1.0, WHITE)
+            })
+            .collect();
+        let mut world = World::new(Physics::default(), open_box(), particles);
+        for _ in 0..300 {
+            world.step(DT);
+        }
+
+        let settled: Vec<Vec2> = world.particles.iter().map(|p| p.position).collect();
+        for _ in 0..300 {
+            world.step(DT);
+            let fastest = world
+                .particles
+                .iter()
+                .map(|p| p.velocity.length())
+                .fold(0.0, f32::max);
+            assert!(fastest < 0.01, "a box still moving at {fastest} m/s");
+        }
+        for (p, before) in world.particles.iter().zip(settled) {
+            assert!(
+                p.position.distance(before) < 1e-3,
+                "box crept from {before} to {}",
+                p.position
+            );
+            assert_eq!(p.position.x, 10.0, "stack leaned over");
+        }
+        let slop = world.physics.correction_slop;
+        for pair in world.particles.windows(2) {
+            let gap = pair[1].position.y - pair[0].position.y;
+            assert!(
+                gap > 1.0 - slop,
+                "boxes sunk {} m into each other",
+                1.0 - gap
+            );
+        }
+    }
+
+    #[test]
+    fn settled_piles_fall_asleep_and_stay_put() {
+        let piles = [
+            ("two-ball stack", stack(2, Physics::default())),
+            ("five-ball stack", stack(5, Physics::default())),
+            ("corner pile", corner_pile()),
+        ];
+        for (name, mut world) in piles {
+            for _ in 0..900 {
+                world.step(DT);
+            }
+            let count = world.particles.len();
+            assert_eq!(world.sleeping_count(), count, "{name} still awake");
+
+            let settled: Vec<Vec2> = world.particles.iter().map(|p| p.position).collect();
+            for _ in 0..120 {
+                world.step(DT);
+            }
+            let now: Vec<Vec2> = world.particles.iter().map(|p| p.position).collect();
+            assert_eq!(now, settled, "{name} jittered in its sleep");
+        }
+    }
+
+    #[test]
+    fn a_hit_wakes_a_sleeping_stack() {
+        let mut world = stack(5, Physics::default());
+        for _ in 0..600 {
+            world.step(DT);
+        }
+        assert_eq!(world.sleeping_count(), 5);
+
+        world.particles.push(ball(Vec2::new(10.0, 9.0), Vec2::ZERO));
+        let mut woken = [false; 5];
+        for _ in 0..120 {
+            world.step(DT);
+            for (woke, p) in woken.iter_mut().zip(&world.particles) {
+                *woke |= !p.is_sleeping();
+            }
+        }
+        assert!(woken.iter().all(|&woke| woke), "stayed asleep: {woken:?}");
+
+        // And the pile, one ball taller now, settles again
+        for _ in 0..900 {
+            world.step(DT);
+        }
+        assert_eq!(world.sleeping_count(), world.particles.len());
+    }
+
+    #[test]
+    fn removing_the_base_of_a_sleeping_stack_drops_the_rest() {
+        // Clicked away, or run out of lifetime
+        for expire in [false, true] {
+            let mut world = stack(5, Physics::default());
+            for _ in 0..600 {
+                world.step(DT);
+            }
+            assert_eq!(world.sleeping_count(), 5);
+
+            let resting = world.particles[1].position.y;
+            if expire {
+                let base = &mut world.particles[0];
+                base.lifetime = Some(base.age);
+                world.step(DT);
+            } else {
+                world.remove_particle(0);
+            }
+            assert_eq!(world.particles.len(), 4);
+            assert_eq!(world.sleeping_count(), 0, "expire: {expire}");
+
+            for _ in 0..120 {
+                world.step(DT);
+            }
+            for (k, p) in world.particles.iter().enumerate() {
+                let fallen = resting + k as f32 - p.position.y;
+                assert!(
+                    (fallen - 1.0).abs() < 0.05,
+                    "expire: {expire}, ball {k} fell {fallen} m"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn ball_spinning_on_the_floor_stops_and_sleeps() {
+        let mut spinning = ball(Vec2::new(10.0, 1.5), Vec2::ZERO);
+        spinning.angular_velocity = 10.0;
+        let mut world = World::new(Physics::default(), open_box(), vec![spinning]);
+        for _ in 0..900 {
+            world.step(DT);
+        }
+        assert!(
+            world.particles[0].is_sleeping(),
+            "still spinning at {} rad/s",
+            world.particles[0].angular_velocity
+        );
+    }
+
+    fn count_events(world: &mut World, steps: usize, index: usize) -> usize {
+        let mut count = 0;
+        for _ in 0..steps {
+            world.step(DT);
+            count += world
+                .collision_events()
+                .iter()
+                .filter(|e| e.involves(index))
+                .count();
+        }
+        count
+    }
+
+    #[test]
+    fn resting_pair_reports_no_hits() {
+        // Kept awake, so the contacts are solved every step
+        let physics = Physics {
+            sleep_steps: 0,
+            ..Physics::default()
+        };
+        let mut world = stack(2, physics);
+        for _ in 0..120 {
+            world.step(DT);
+        }
+        let mut events = 0;
+        for _ in 0..600 {
+            world.step(DT);
+            events += world.collision_events().len();
+        }
+        assert_eq!(events, 0);
+    }
+
+    #[test]
+    fn dropped_ball_reports_exactly_one_hit() {
+        // Nothing bounces, so each fall ends in a single hit
+        let physics = Physics {
+            restitution: 0.0,
+            boundary_restitution: Some(0.0),
+            ..Physics::default()
+        };
+        let particles = vec![ball(Vec2::new(10.0, 6.0), Vec2::ZERO)];
+        let mut world = World::new(physics.clone(), open_box(), particles);
+        assert_eq!(count_events(&mut world, 600, 0), 1, "onto the floor");
+
+        let mut world = stack(1, physics);
+        world.particles.push(ball(Vec2::new(10.0, 6.0), Vec2::ZERO));
+        assert_eq!(count_events(&mut world, 600, 1), 1, "onto a resting ball");
+    }
+
+    // Serial against threaded over a few steps of a scattered N-body pile.
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_mutual_gravity_matches_serial_bit_for_bit() {
+        let physics = Physics {
+            gravitational_constant: 10.0,
+            ..Physics::default()
+        };
+        let mut world = World::new(physics, open_box(), Vec::new());
+        assert_eq!(world.scatter_particles(150, 0.1..0.3), 150);
+        let bits = |forces: Vec<Vec2>| -> Vec<[u32; 2]> {
+            forces
+                .iter()
+                .map(|f| f.to_array().map(f32::to_bits))
+                .collect()
+        };
+        for step in 0..30 {
+            assert_eq!(
+                bits(world.mutual_gravity()),
+                bits(world.mutual_gravity_parallel()),
+                "serial and parallel pulls differ at step {step}"
+            );
+            world.step(DT);
+        }
+    }
+
+    #[test]
+    fn mutual_gravity_pulls_two_particles_together() {
+        let physics = Physics {
+            gravity: Vec2::ZERO,
+            drag_coefficient: 0.0,
+            gravitational_constant: 10.0,
+            sleep_steps: 0,
+            ..Physics::default()
+        };
+        let particles = vec![
+            ball(Vec2::new(7.0, 10.0), Vec2::ZERO),
+            ball(Vec2::new(13.0, 10.0), Vec2::ZERO),
+        ];
+        let mut world = World::new(physics, open_box(), particles);
+
+        let mut previous_gap = 6.0;
+        let mut previous_speed = 0.0;
+        for _ in 0..60 {
+            world.step(DT);
+            let [a, b] = &world.particles[..] else {
+                unreachable!()
+            };
+            let gap = b.position.x - a.position.x;
+            assert!(gap < previous_gap, "gap grew to {gap}");
+            assert!(
+                a.velocity.x > previous_speed,
+                "not speeding up toward the other"
+            );
+            assert!(
+                (a.velocity + b.velocity).length() < 1e-5,
+                "momentum not conserved"
+            );
+            assert!(a.position.y == 10.0 && b.position.y == 10.0);
+            (previous_gap, previous_speed) = (gap, a.velocity.x);
+        }
+    }
+
+    #[test]
+    fn query_radius_includes_particles_exactly_at_the_radius() {
+        let center = Vec2::new(10.0, 10.0);
+        let mut particles: Vec<_> = [(10.0, 10.0), (13.0, 14.0), (10.0, 5.0), (15.001, 10.0)]
+            .into_iter()
+            .map(|(x, y)| ball(Vec2::new(x, y), Vec2::ZERO))
+            .collect();
+        let mut world = World::new(Physics::default(), open_box(), particles.clone());
+        // Few particles against many cells: checked one by one
+        assert_eq!(world.query_radius(center, 5.0), vec![0, 1, 2]);
+        assert!(world.spatial_hash.cells_within(center, 5.0) > world.particles.len());
+
+        // Enough particles that the grid answers it
+        particles.extend((0..200).map(|k| ball(Vec2::new(1.0, 0.1 * k as f32), Vec2::ZERO)));
+        let mut world = World::new(Physics::default(), open_box(), particles);
+        assert_eq!(world.query_radius(center, 5.0), vec![0, 1, 2]);
+        assert!(world.spatial_hash.cells_within(center, 5.0) <= world.particles.len());
+        assert_eq!(world.query_radius(center, 4.999), vec![0]);
+    }
+
+    #[test]
+    fn three_dropped_particles_settle_into_a_stack() {
+        let particles = (0..3)
+            .map(|k| ball(Vec2::new(10.0, 2.0 + 2.0 * k as f32), Vec2::ZERO))
+            .collect();
+        let mut world = World::new(Physics::default(), open_box(), particles);
+        for _ in 0..600 {
+            world.step(DT);
+        }
+
+        let overlap = deepest_overlap(&world);
+        assert!(
+            overlap < world.physics.correction_slop,
+            "overlap {overlap} m"
+        );
+        for (k, p) in world.particles.iter().enumerate() {
+            let resting_height = world.boundary.bottom + 0.5 + k as f32;
+            assert!(
+                (p.position.y - resting_height).abs() < 0.05,
+                "particle {k} at {}, not resting at {resting_height}",
+                p.position
+            );
+            assert!(p.velocity.length() < 0.01, "particle {k} still moving");
+        }
+    }
+
+    #[test]
+    fn ball_dropped_on_a_peg_bounces_off_to_the_side() {
+        let peg = StaticCircle::new(Vec2::new(10.0, 8.0), 0.5);
+        let (peg_position, reach) = (peg.position, peg.radius + 0.5);
+        let particles = vec![ball(Vec2::new(10.1, 12.0), Vec2::ZERO)];
+        let mut world = World::new(Physics::default(), open_box(), particles);
+        world.obstacles.push(peg);
+
+        let mut bounced = false;
+        for _ in 0..120 {
+            world.step(DT);
+            let p = &world.particles[0];
+            let gap = p.position.distance(peg_position) - reach;
+            assert!(gap > -1e-4, "sank {} m into the peg", -gap);
+            // Going up while still at the peg's height, long before the floor
+            bounced |= p.velocity.y > 0.0 && p.position.y > peg_position.y;
+        }
+        assert!(bounced, "never bounced off the peg");
+        let p = &world.particles[0];
+        assert!(
+            p.position.x > 11.0,
+            "only got sideways to x = {}",
+            p.position.x
+        );
+    }
+
+    #[test]
+    fn ice_on_ice_slides_further_than_rubber_on_rubber() {
+        // A box pushed along a pinned slab of the same material
+        let slid = |material: &Material| {
+            let slab = Particle::new_box(
+                Vec2::new(10.0, 1.5),
+                Vec2::ZERO,
+                Vec2::new(8.0, 0.5),
+                1.0,
+                WHITE,
+            )
+            .with_material(material)
+            .pin();
+            let block = Particle::new_box(
+                Vec2::new(3.0, 2.5),
+                Vec2::new(3.0, 0.0),
+                Vec2::splat(0.5),
+                1.0,
+                WHITE,
+            )
+            .with_material(material);
+            let mut world = World::new(Physics::default(), open_box(), vec![slab, block]);
+            for _ in 0..120 {
+                world.step(DT);
+            }
+            world.particles[1].position.x - 3.0
+        };
+        let (ice, rubber) = (slid(&ICE), slid(&RUBBER));
+        assert!(ice > 2.0 * rubber, "ice slid {ice} m, rubber {rubber} m");
+    }
+
+    #[test]
+    fn box_rests_flat_on_the_floor_without_jitter() {
+        // Kept awake, so sleep can't hide any jitter
+        let physics = Physics {
+            sleep_steps: 0,
+            ..Physics::default()
+        };
+        let half_extents = Vec2::new(1.0, 0.25);
+        let particles = vec![Particle::new_box(
+            Vec2::new(10.0, 4.0),
+            Vec2::ZERO,
+            half_extents,
+            2.0,
+            WHITE,
+        )];
+        let mut world = World::new(physics, open_box(), particles);
+        for _ in 0..300 {
+            world.step(DT);
+        }
+
+        let floor = world.boundary.bottom + half_extents.y;
+        let mut previous = world.particles[0].position;
+        for _ in 0..300 {
+            world.step(DT);
+            let p = &world.particles[0];
+            assert!(
+                (p.position.y - floor).abs() < 1e-3,
+                "resting at {} instead of {floor}",
+                p.position.y
+            );
+            assert!(
+                p.position.distance(previous) < 1e-5,
+                "jittered from {previous} to {}",
+                p.position
+            );
+            assert!(p.velocity.length() < 1e-3, "moving at {}", p.velocity);
+            previous = p.position;
+        }
+    }
+
+    #[test]
+    fn mutual_gravity_adds_to_uniform_gravity() {
+        let physics = Physics {
+            drag_coefficient: 0.0,
+            gravitational_constant: 10.0,
+            sleep_steps: 0,
+            ..Physics::default()
+        };
+        let particles = vec![
+            ball(Vec2::new(7.0, 15.0), Vec2::ZERO),
+            ball(Vec2::new(13.0, 15.0), Vec2::ZERO),
+        ];
+        let mut world = World::new(physics, open_box(), particles);
+        let steps = 30;
+        for _ in 0..steps {
+            world.step(DT);
+        }
+
+        // The pair's own pulls cancel, so together they fall just as freely
+        let [a, b] = &world.particles[..] else {
+            unreachable!()
+        };
+        let fall_speed = 9.8 * DT * steps as f32;
+        let mean_velocity = (a.velocity + b.velocity) * 0.5;
+        assert!(
+            (mean_velocity.y + fall_speed).abs() < 1e-3,
+            "falling at {} m/s instead of {fall_speed}",
+            -mean_velocity.y
+        );
+        assert!(mean_velocity.x.abs() < 1e-5);
+        assert!(
+            a.velocity.x > 0.0 && b.velocity.x < 0.0,
+            "not drawn together"
+        );
+        assert!(b.position.x - a.position.x < 6.0);
+    }
+
+    // A small particle fired at 480 m/s, 8 m a step, at a resting one 4 m
+    // ahead, stepped once.
+    fn shot(ccd: bool) -> World {
+        let physics = Physics {
+            gravity: Vec2::ZERO,
+            drag_coefficient: 0.0,
+            ccd,
+            ..Physics::default()
+        };
+        let bullet = Particle::new(Vec2::new(3.0, 10.0), Vec2::new(480.0, 0.0), 0.1, 0.1, WHITE);
+        let target = ball(Vec2::new(7.0, 10.0), Vec2::ZERO);
+        let mut world = World::new(physics, open_box(), vec![bullet, target]);
+        world.step(DT);
+        world
+    }
+
+    #[test]
+    fn ccd_stops_a_fast_particle_tunneling_through_another() {
+        // Without the sweep it jumps clean over the target
+        let world = shot(false);
+        assert!(world.particles[0].position.x > 8.0);
+        assert_eq!(world.particles[1].velocity, Vec2::ZERO);
+
+        let world = shot(true);
+        let [bullet, target] = &world.particles[..] else {
+            unreachable!()
+        };
+        assert!(
+            bullet.position.x < target.position.x,
+            "passed through to x = {}",
+            bullet.position.x
+        );
+        let gap = target.position.distance(bullet.position) - 0.6;
+        assert!(
+            gap > -world.physics.correction_slop,
+            "overlapping by {}",
+            -gap
+        );
+        assert!(target.velocity.x > 0.0, "target never hit");
+        assert!(bullet.velocity.x < 480.0);
+    }
+
+    #[test]
+    fn cached_boundary_steps_the_same_as_rebuilding_it() {
+        // The window the other tests fix the world with, so whichever runs
+        // first they all see the same world
+        set_world(WorldConfig::for_window(
+            Vec2::new(800.0, 600.0),
+            SIM_MIN_WIDTH,
+        ));
+        let particles: Vec<_> = (0..6)
+            .map(|k| {
+                let k = k as f32;
+                ball(Vec2::new(3.0 + 2.5 * k, 4.0 + k), Vec2::new(4.0 - k, 1.0))
+            })
+            .collect();
+        let mut cached = World::new(Physics::default(), Boundary::new(), particles.clone());
+        let mut rebuilt = World::new(Physics::default(), Boundary::new(), particles);
+        for step in 0..300 {
+            cached.step(DT);
+            // The old path: a boundary built from the world size every step
+            rebuilt.boundary = Boundary::new();
+            rebuilt.step(DT);
+            for (a, b) in cached.particles.iter().zip(&rebuilt.particles) {
+                assert_eq!(a.position, b.position, "diverged at step {step}");
+            }
+        }
+    }
+
+    // Launches a ball at `velocity` with nothing else acting on it and
+    // steps until it bounces off a wall, giving back its velocity then.
+    fn launch(position: Vec2, velocity: Vec2) -> Vec2 {
+        let physics = Physics {
+            gravity: Vec2::ZERO,
+            drag_coefficient: 0.0,
+            ..Physics::default()
+        };
+        let mut world = World::new(physics, open_box(), vec![ball(position, velocity)]);
+        for _ in 0..60 {
+            world.step(DT);
+            if !world.collision_events().is_empty() {
+                return world.particles[0].velocity;
+            }
+        }
+        panic!("never reached the wall");
+    }
+
+    #[test]
+    fn ceiling_and_left_wall_slow_sliding_like_the_floor() {
+        let after = launch(Vec2::new(10.0, 15.0), Vec2::new(3.0, 20.0));
+        assert!(after.y < 0.0, "didn't bounce off the ceiling");
+        assert!(
+            after.x > 0.0 && after.x < 3.0,
+            "ceiling left it sliding at {} m/s",
+            after.x
+        );
+
+        let after = launch(Vec2::new(5.0, 10.0), Vec2::new(-20.0, 3.0));
+        assert!(after.x > 0.0, "didn't bounce off the left wall");
+        assert!(
+            after.y > 0.0 && after.y < 3.0,
+            "left wall left it sliding at {} m/s",
+            after.y
+        );
+    }
+
+    #[test]
+    fn step_falls_by_one_euler_step_and_removes_expired_particles() {
+        let physics = Physics {
+            drag_coefficient: 0.0,
+            ..Physics::default()
+        };
+        let short_lived = |x| {
+            let mut p = ball(Vec2::new(x, 10.0), Vec2::ZERO);
+            p.lifetime = Some(1.5 * DT);
+            p
+        };
+        let particles = vec![
+            short_lived(5.0),
+            ball(Vec2::new(10.0, 10.0), Vec2::new(1.0, 0.0)),
+            short_lived(15.0),
+        ];
+        let mut world = World::new(physics, open_box(), particles);
+
+        assert!(world.step(DT).is_empty());
+        let p = &world.particles[1];
+        let velocity = Vec2::new(1.0, -9.8 * DT);
+        assert!(
+            p.velocity.abs_diff_eq(velocity, 1e-6),
+            "velocity {}",
+            p.velocity
+        );
+        let position = Vec2::new(10.0, 10.0) + velocity * DT;
+        assert!(
+            p.position.abs_diff_eq(position, 1e-6),
+            "position {}",
+            p.position
+        );
+
+        // Highest index first, and the survivor moves down to index 0
+        assert_eq!(world.step(DT), vec![2, 0]);
+        assert_eq!(world.particles.len(), 1);
+        assert_eq!(world.particles[0].velocity.x, 1.0);
+    }
+
+    // Steady push on the heavy particles only, to tell it apart from gravity.
+    struct HeavyOnly(Vec2);
+
+    impl Force for HeavyOnly {
+        fn apply(&self, particle: &Particle, _particles: &[Particle]) -> Vec2 {
+            if particle.mass > 1.0 {
+                self.0
+            } else {
+                Vec2::ZERO
+            }
+        }
+    }
+
+    #[test]
+    fn extra_forces_add_to_the_configured_ones() {
+        let physics = Physics {
+            drag_coefficient: 0.0,
+            ..Physics::default()
+        };
+        let heavy = Particle::new(Vec2::new(5.0, 10.0), Vec2::ZERO, 0.5, 2.0, WHITE);
+        let particles = vec![ball(Vec2::new(15.0, 10.0), Vec2::ZERO), heavy];
+        let mut world = World::new(physics, open_box(), particles);
+        world.forces.push(Box::new(HeavyOnly(Vec2::new(3.0, 0.0))));
+        world.step(DT);
+
+        let gravity = Vec2::new(0.0, -9.8) * DT;
+        let [light, heavy] = &world.particles[..] else {
+            unreachable!()
+        };
+        assert!(
+            light.velocity.abs_diff_eq(gravity, 1e-6),
+            "{}",
+            light.velocity
+        );
+        let pushed = gravity + Vec2::new(3.0, 0.0) * DT;
+        assert!(
+            heavy.velocity.abs_diff_eq(pushed, 1e-6),
+            "{}",
+            heavy.velocity
+        );
+    }
+
+    // Advances `world` by a frame of `frame_time`, giving back the steps
+    // taken, as counted by the step callback, and the time left banked.
+    fn frame(world: &mut World, frame_time: f32, max_steps: u32) -> (u32, f32) {
+        let mut stepped = 0;
+        let steps = world.advance(frame_time, 1.0, DT, max_steps, |world| {
+            world.step(DT);
+            stepped += 1;
+        });
+        assert_eq!(steps, stepped);
+        (steps, world.accumulator())
+    }
+
+    #[test]
+    fn a_frame_runs_the_steps_it_pays_for_and_banks_the_rest() {
+        let mut world = World::new(Physics::default(), open_box(), Vec::new());
+        let (steps, left) = frame(&mut world, 2.5 * DT, MAX_STEPS_PER_FRAME);
+        assert_eq!(steps, 2);
+        assert!((left - 0.5 * DT).abs() < 1e-6, "{left} s left over");
+
+        // The leftover counts toward the next frame
+        let (steps, left) = frame(&mut world, 0.75 * DT, MAX_STEPS_PER_FRAME);
+        assert_eq!(steps, 1);
+        assert!((left - 0.25 * DT).abs() < 1e-6, "{left} s left over");
+
+        // Too short for a step at all
+        assert_eq!(frame(&mut world, 0.5 * DT, MAX_STEPS_PER_FRAME).0, 0);
+
+        // Nothing banked is left to catch up on once cleared
+        world.clear_accumulator();
+        assert_eq!(frame(&mut world, 0.75 * DT, MAX_STEPS_PER_FRAME).0, 0);
+    }
+
+    #[test]
+    fn a_long_frame_runs_a_bounded_number_of_steps() {
+        let most = (MAX_FRAME_TIME / DT).ceil() as u32;
+        for stall in [1.0, 10.0, 3600.0] {
+            let mut world = World::new(Physics::default(), open_box(), Vec::new());
+            let (steps, _) = frame(&mut world, stall, MAX_STEPS_PER_FRAME);
+            assert!(steps <= most, "a {stall} s frame ran {steps} steps");
+        }
+    }
+
+    #[test]
+    fn past_the_step_cap_the_rest_of_the_frame_is_dropped() {
+        let mut world = World::new(Physics::default(), open_box(), Vec::new());
+        let (steps, left) = frame(&mut world, MAX_FRAME_TIME, 4);
+        assert_eq!(steps, 4);
+        assert_eq!(left, 0.0);
+    }
+}